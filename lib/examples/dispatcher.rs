@@ -0,0 +1,39 @@
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use telegram_bot::prelude::*;
+use telegram_bot::{Api, Dispatcher, Filter, HandlerResult, MessageKind};
+
+#[tokio::main]
+async fn main() {
+    let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
+    let api = Api::new(token);
+
+    // Count the greeted users in the shared state of the dispatcher.
+    let mut dispatcher = Dispatcher::new(&api, AtomicUsize::new(0));
+    dispatcher
+        .before(|_, _, update| {
+            println!("update {}", update.id);
+            true
+        })
+        .on_command("start", |api, greeted, message| async move {
+            let count = greeted.fetch_add(1, Ordering::SeqCst) + 1;
+            let reply = format!(
+                "Hi, {}! You are visitor #{}",
+                message.from.first_name, count
+            );
+            api.send(message.text_reply(reply)).await?;
+            HandlerResult::Ok(())
+        })
+        .on_message(Filter::private(), |api, _, message| async move {
+            if let MessageKind::Text { ref data, .. } = message.kind {
+                let reply = format!("You just wrote '{}'", data);
+                api.send(message.text_reply(reply)).await?;
+            }
+            HandlerResult::Ok(())
+        })
+        .on_error(|error| eprintln!("error: {}", error));
+
+    // Errors don't stop the dispatcher, it runs as long as the stream.
+    dispatcher.run(api.stream()).await;
+}
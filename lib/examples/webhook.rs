@@ -0,0 +1,30 @@
+use std::env;
+
+use futures::StreamExt;
+use telegram_bot::*;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
+    let url = env::var("TELEGRAM_WEBHOOK_URL").expect("TELEGRAM_WEBHOOK_URL not set");
+    let api = Api::new(token);
+
+    // Tell Telegram where to deliver updates, the url should point
+    // to a reverse proxy forwarding requests to this server.
+    api.send(SetWebhook::new(url)).await?;
+
+    let mut webhook = api.webhook(([0, 0, 0, 0], 8080).into());
+    while let Some(update) = webhook.next().await {
+        let update = update?;
+        if let UpdateKind::Message(message) = update.kind {
+            if let MessageKind::Text { ref data, .. } = message.kind {
+                api.send(message.text_reply(format!(
+                    "Hi, {}! You just wrote '{}'",
+                    &message.from.first_name, data
+                )))
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -2,28 +2,294 @@ use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
-use std::time::Duration;
+use std::env;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use futures::{Future, FutureExt};
-use tokio::time::timeout;
+use bytes::Bytes;
+use futures::{future, stream, Future, FutureExt, Stream, StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tracing_futures::Instrument;
 
-use telegram_bot_raw::{HttpRequest, Request, ResponseType};
+use telegram_bot_raw::{
+    ApiUrl, Body, BotToken, File, GetFile, HttpRequest, Method, MultipartValue, Request,
+    RequestUrl, ResponseType, ToFileRef, Update,
+};
 
-use crate::connector::{default_connector, Connector};
+use crate::bulk::SendAll;
+use crate::connector::{default_connector, ByteStream, Connector, Layer, UploadProgress};
 use crate::errors::{Error, ErrorKind};
+use crate::limiter::RateLimiter;
+use crate::metrics::{Metrics, NoopMetrics};
+use crate::migration::{migrate_failed_request, migrate_request, observe_update, MigrationStore};
+use crate::retry::RetryPolicy;
+use crate::runtime::{default_runtime, timeout, timeout_stream, Runtime};
 use crate::stream::UpdatesStream;
+use crate::webhook::Webhook;
 
 /// Main type for sending requests to the Telegram bot API.
 #[derive(Clone)]
 pub struct Api(Arc<ApiInner>);
 
 struct ApiInner {
-    token: String,
+    token: BotToken,
+    api_url: ApiUrl,
+    local_server: bool,
+    timeout: Option<Duration>,
     connector: Box<dyn Connector>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    migrations: Option<Arc<dyn MigrationStore>>,
+    metrics: Arc<dyn Metrics>,
+    runtime: Arc<dyn Runtime>,
     next_request_id: AtomicUsize,
 }
 
+/// Builder for `Api` instances with non-default configuration.
+///
+/// # Example
+///
+/// ```rust
+/// use telegram_bot::{Api, RetryPolicy};
+///
+/// # fn main() {
+/// # let telegram_token = "token";
+/// let api = Api::builder(telegram_token)
+///     .retry_policy(RetryPolicy::new())
+///     .build();
+/// # }
+/// ```
+pub struct ApiBuilder {
+    token: BotToken,
+    api_url: ApiUrl,
+    local_server: bool,
+    timeout: Option<Duration>,
+    connector: Option<Box<dyn Connector>>,
+    layers: Vec<Box<dyn Layer>>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    migrations: Option<Arc<dyn MigrationStore>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    runtime: Option<Arc<dyn Runtime>>,
+}
+
+impl ApiBuilder {
+    /// Create a new `ApiBuilder` for the given token.
+    pub fn new<T: Into<BotToken>>(token: T) -> Self {
+        ApiBuilder {
+            token: token.into(),
+            api_url: ApiUrl::default(),
+            local_server: false,
+            timeout: None,
+            connector: None,
+            layers: Vec::new(),
+            retry_policy: None,
+            rate_limiter: None,
+            migrations: None,
+            metrics: None,
+            runtime: None,
+        }
+    }
+
+    /// Send requests to the Bot API server at `base_url`, e.g. `https://api.telegram.org/`.
+    ///
+    /// Defaults to `https://api.telegram.org/`, pass [`ApiUrl::from_env`] to [`api_url`]
+    /// to take it from the `TELEGRAM_API_URL` environment variable instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use telegram_bot::Api;
+    ///
+    /// # fn main() {
+    /// # let telegram_token = "token";
+    /// let api = Api::builder(telegram_token)
+    ///     .base_url("http://localhost:8081/")
+    ///     .test_environment(true)
+    ///     .build();
+    /// # }
+    /// ```
+    ///
+    /// [`ApiUrl::from_env`]: ../telegram_bot_raw/url/struct.ApiUrl.html#method.from_env
+    /// [`api_url`]: #method.api_url
+    pub fn base_url<T: Into<String>>(mut self, base_url: T) -> Self {
+        self.api_url.base_url(base_url);
+        self
+    }
+
+    /// Download files from `file_base_url` instead of the base URL of the server.
+    pub fn file_base_url<T: Into<String>>(mut self, file_base_url: T) -> Self {
+        self.api_url.file_base_url(file_base_url);
+        self
+    }
+
+    /// Send requests to the Telegram test environment.
+    ///
+    /// Defaults to `false`.
+    pub fn test_environment(mut self, test_environment: bool) -> Self {
+        self.api_url.test_environment(test_environment);
+        self
+    }
+
+    /// Use the complete server configuration from `api_url`.
+    pub fn api_url(mut self, api_url: ApiUrl) -> Self {
+        self.api_url = api_url;
+        self
+    }
+
+    /// Talk to a self-hosted [Bot API server](https://github.com/tdlib/telegram-bot-api)
+    /// running in local mode on the same machine.
+    ///
+    /// Files are then downloaded directly from the local paths returned by `getFile`
+    /// and files uploaded from a path are passed to the server by their `file://` URI.
+    /// Use [`LogOut`] to move the bot from the cloud server to a local one first.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use telegram_bot::Api;
+    ///
+    /// # fn main() {
+    /// # let telegram_token = "token";
+    /// let api = Api::builder(telegram_token)
+    ///     .base_url("http://localhost:8081/")
+    ///     .local_server(true)
+    ///     .build();
+    /// # }
+    /// ```
+    ///
+    /// [`LogOut`]: ../telegram_bot_raw/requests/log_out/struct.LogOut.html
+    pub fn local_server(mut self, local_server: bool) -> Self {
+        self.local_server = local_server;
+        self
+    }
+
+    /// Fail every attempt to send a request which takes longer than `timeout`.
+    /// File downloads fail when the response or any chunk of its body takes longer.
+    ///
+    /// Requests sent with [`Api::send_timeout`] use their own timeout instead.
+    /// There is no timeout by default.
+    ///
+    /// [`Api::send_timeout`]: struct.Api.html#method.send_timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Use a custom connector instead of the default one.
+    pub fn connector(mut self, connector: Box<dyn Connector>) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Wrap the connector with `layer`.
+    ///
+    /// Layers are applied in order, the first added layer is the outermost one
+    /// and sees requests first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use telegram_bot::Api;
+    /// use telegram_bot::connector::{InspectLayer, TimingLayer};
+    ///
+    /// # fn main() {
+    /// # let telegram_token = "token";
+    /// let api = Api::builder(telegram_token)
+    ///     .layer(TimingLayer::new())
+    ///     .layer(InspectLayer::new().request(|request| println!("{}", request.name())))
+    ///     .build();
+    /// # }
+    /// ```
+    pub fn layer<L: Layer + 'static>(mut self, layer: L) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Resend requests rejected by the flood control according to `policy`.
+    ///
+    /// Requests are not retried by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Queue outgoing requests according to `limiter`, so they stay within
+    /// the Telegram limits for broadcasts.
+    ///
+    /// Requests are sent immediately by default.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Remember group to supergroup migrations in `store` and transparently resend
+    /// requests to migrated groups to their supergroups.
+    ///
+    /// Migrations are learned both from failed requests and from
+    /// `MigrateToChatId`/`MigrateFromChatId` messages in the update stream.
+    pub fn migrations(mut self, store: Arc<dyn MigrationStore>) -> Self {
+        self.migrations = Some(store);
+        self
+    }
+
+    /// Report request latencies, errors and received updates to `metrics`.
+    ///
+    /// Measurements are discarded by default.
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Replace the metrics with `wrap` applied to the configured ones.
+    pub(crate) fn wrap_metrics<F>(mut self, wrap: F) -> Self
+    where
+        F: FnOnce(Arc<dyn Metrics>) -> Arc<dyn Metrics>,
+    {
+        let metrics = self.metrics.take().unwrap_or_else(|| Arc::new(NoopMetrics));
+        self.metrics = Some(wrap(metrics));
+        self
+    }
+
+    /// Spawn background requests, wait and read local files with `runtime`.
+    ///
+    /// Defaults to the runtime selected by the enabled features, see [`default_runtime`].
+    ///
+    /// [`default_runtime`]: runtime/fn.default_runtime.html
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Create the configured `Api` instance.
+    pub fn build(self) -> Api {
+        let connector = self.connector.unwrap_or_else(default_connector);
+        let connector = self
+            .layers
+            .iter()
+            .rev()
+            .fold(connector, |inner, layer| layer.layer(inner));
+
+        Api(Arc::new(ApiInner {
+            token: self.token,
+            api_url: self.api_url,
+            local_server: self.local_server,
+            timeout: self.timeout,
+            connector,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            migrations: self.migrations,
+            metrics: self.metrics.unwrap_or_else(|| Arc::new(NoopMetrics)),
+            runtime: self.runtime.unwrap_or_else(default_runtime),
+            next_request_id: AtomicUsize::new(0),
+        }))
+    }
+}
+
 impl Api {
     /// Create a new `Api` instance.
     ///
@@ -39,17 +305,211 @@ impl Api {
     /// let api = Api::new(telegram_token);
     /// # }
     /// ```
-    pub fn new<T: AsRef<str>>(token: T) -> Self {
-        Self::with_connector(token, default_connector())
+    pub fn new<T: Into<BotToken>>(token: T) -> Self {
+        Self::builder(token).build()
     }
 
     /// Create a new `Api` instance wtih custom connector.
-    pub fn with_connector<T: AsRef<str>>(token: T, connector: Box<dyn Connector>) -> Self {
-        Api(Arc::new(ApiInner {
-            token: token.as_ref().to_string(),
-            connector,
-            next_request_id: AtomicUsize::new(0),
-        }))
+    pub fn with_connector<T: Into<BotToken>>(token: T, connector: Box<dyn Connector>) -> Self {
+        Self::builder(token).connector(connector).build()
+    }
+
+    /// Create a new `ApiBuilder` to configure an `Api` instance.
+    pub fn builder<T: Into<BotToken>>(token: T) -> ApiBuilder {
+        ApiBuilder::new(token)
+    }
+
+    /// Returns the Bot API server configuration.
+    pub fn api_url(&self) -> &ApiUrl {
+        &self.0.api_url
+    }
+
+    /// Returns URL to download the file from, if the file is available for download.
+    ///
+    /// Files of a local Bot API server have no URL, see [`local_file_path`].
+    ///
+    /// [`local_file_path`]: #method.local_file_path
+    pub fn file_url(&self, file: &File) -> Option<String> {
+        if self.0.local_server {
+            return None;
+        }
+        file.get_url(&self.0.api_url, &self.0.token)
+    }
+
+    /// Returns the path of the file on the local filesystem,
+    /// if the file is stored by a local Bot API server.
+    pub fn local_file_path(&self, file: &File) -> Option<PathBuf> {
+        match file.file_path {
+            Some(ref file_path) if self.0.local_server => Some(PathBuf::from(file_path)),
+            _ => None,
+        }
+    }
+
+    /// Download the file through the connector, yielding its contents in chunks.
+    /// Files of a local Bot API server are read from the local filesystem instead.
+    ///
+    /// The file has to be obtained with [`GetFile`] first, see [`download_file_ref`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, GetFile, FileRef};
+    /// use futures::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let api = Api::new("token");
+    /// # if false {
+    /// let file = api.send(GetFile::new(FileRef::from("file_id"))).await.unwrap();
+    /// let mut download = api.download_file(&file);
+    /// while let Some(chunk) = download.next().await {
+    ///     println!("received {} bytes", chunk.unwrap().len());
+    /// }
+    /// # }
+    /// # }
+    /// ```
+    ///
+    /// [`GetFile`]: ../telegram_bot_raw/requests/get_file/struct.GetFile.html
+    /// [`download_file_ref`]: #method.download_file_ref
+    pub fn download_file(&self, file: &File) -> impl Stream<Item = Result<Bytes, Error>> + Send {
+        let download = match file.file_path {
+            Some(ref file_path) if self.0.local_server => {
+                tracing::trace!(file_id = %file.file_id, "reading local file");
+                let chunks = self
+                    .0
+                    .runtime
+                    .read_file(PathBuf::from(file_path))
+                    .map_err(|err| Error::from(ErrorKind::from(err)));
+                future::ok::<ByteStream, Error>(chunks.boxed()).boxed()
+            }
+            Some(ref file_path) => {
+                let request = HttpRequest {
+                    url: RequestUrl::file(file_path.as_str()),
+                    method: Method::Get,
+                    body: Body::Empty,
+                };
+                tracing::trace!(file_id = %file.file_id, "downloading file");
+                let response = self
+                    .0
+                    .connector
+                    .download(&self.0.api_url, &self.0.token, request);
+                match self.0.timeout {
+                    Some(duration) => {
+                        let runtime = self.0.runtime.clone();
+                        async move {
+                            match timeout(&*runtime, duration, response).await {
+                                Some(body) => Ok(timeout_stream(runtime, duration, body?)),
+                                None => Err(ErrorKind::Timeout(duration).into()),
+                            }
+                        }
+                        .boxed()
+                    }
+                    None => response,
+                }
+            }
+            None => future::err(ErrorKind::FileUnavailable.into()).boxed(),
+        };
+        stream::once(download).try_flatten()
+    }
+
+    /// Resolve the file with [`GetFile`] and download it, yielding its contents in chunks.
+    ///
+    /// [`GetFile`]: ../telegram_bot_raw/requests/get_file/struct.GetFile.html
+    pub fn download_file_ref<F: ToFileRef>(
+        &self,
+        file: F,
+    ) -> impl Stream<Item = Result<Bytes, Error>> + Send {
+        let api = self.clone();
+        let file = self.send(GetFile::new(file));
+        let download = async move {
+            let file = file.await?;
+            Ok::<ByteStream, Error>(api.download_file(&file).boxed())
+        };
+        stream::once(download).try_flatten()
+    }
+
+    /// Download the file into `writer`, failing as soon as the file turns out
+    /// to be larger than `max_size` bytes. Returns the size of the file.
+    pub async fn download_file_to<W>(
+        &self,
+        file: &File,
+        writer: &mut W,
+        max_size: Option<u64>,
+    ) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut download = self.download_file_limited(file, max_size);
+        let mut size = 0;
+        while let Some(chunk) = download.next().await {
+            let chunk = chunk?;
+            size += chunk.len() as u64;
+            writer.write_all(&chunk).await.map_err(ErrorKind::from)?;
+        }
+        writer.flush().await.map_err(ErrorKind::from)?;
+        Ok(size)
+    }
+
+    /// Download the file to `path`, failing as soon as the file turns out
+    /// to be larger than `max_size` bytes. Returns the size of the file.
+    ///
+    /// The partially written file is removed if the download fails.
+    pub async fn download_file_to_path<P: AsRef<Path>>(
+        &self,
+        file: &File,
+        path: P,
+        max_size: Option<u64>,
+    ) -> Result<u64, Error> {
+        let download = self.download_file_limited(file, max_size);
+        self.0
+            .runtime
+            .write_file(path.as_ref().to_path_buf(), download)
+            .await
+    }
+
+    /// Download the file, failing as soon as it turns out to be larger than `max_size` bytes.
+    fn download_file_limited(&self, file: &File, max_size: Option<u64>) -> ByteStream {
+        let too_large = move |size: u64| match max_size {
+            Some(max_size) if size > max_size => {
+                Some(Error::from(ErrorKind::FileTooLarge(max_size)))
+            }
+            _ => None,
+        };
+        if let Some(err) = file.file_size.and_then(|size| too_large(size.max(0) as u64)) {
+            return stream::once(future::err(err)).boxed();
+        }
+
+        let mut size = 0;
+        self.download_file(file)
+            .map(move |chunk| {
+                let chunk = chunk?;
+                size += chunk.len() as u64;
+                match too_large(size) {
+                    Some(err) => Err(err),
+                    None => Ok(chunk),
+                }
+            })
+            .boxed()
+    }
+
+    /// Returns the store of group to supergroup migrations, if enabled.
+    pub fn migrations(&self) -> Option<&dyn MigrationStore> {
+        self.0.migrations.as_deref()
+    }
+
+    pub(crate) fn metrics(&self) -> &dyn Metrics {
+        &*self.0.metrics
+    }
+
+    pub(crate) fn runtime(&self) -> &dyn Runtime {
+        &*self.0.runtime
+    }
+
+    /// Remember migrations announced by the update.
+    pub(crate) fn observe_update(&self, update: &Update) {
+        if let Some(ref store) = self.0.migrations {
+            observe_update(&**store, update);
+        }
     }
 
     /// Create a stream which produces updates from the Telegram server.
@@ -73,6 +533,32 @@ impl Api {
         UpdatesStream::new(&self)
     }
 
+    /// Create a stream which produces updates delivered by the Telegram server
+    /// to a webhook listening on `addr`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, SetWebhook};
+    /// use futures::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let api: Api = Api::new("token");
+    /// # if false {
+    /// api.send(SetWebhook::new("https://example.com/bot")).await.unwrap();
+    ///
+    /// let mut webhook = api.webhook(([0, 0, 0, 0], 8080).into());
+    /// webhook.path("/bot");
+    /// let update = webhook.next().await;
+    ///     println!("{:?}", update);
+    /// # }
+    /// # }
+    /// ```
+    pub fn webhook(&self, addr: SocketAddr) -> Webhook {
+        Webhook::new(self, addr)
+    }
+
     /// Send a request to the Telegram server and do not wait for a response.
     ///
     /// # Examples
@@ -94,9 +580,11 @@ impl Api {
     pub fn spawn<Req: Request>(&self, request: Req) {
         let api = self.clone();
         if let Ok(request) = request.serialize() {
-            tokio::spawn(async move {
-                let _ = api.send_http_request::<Req::Response>(request).await;
-            });
+            self.0.runtime.spawn(Box::pin(async move {
+                let _ = api
+                    .send_http_request::<Req::Response>(request, api.0.timeout, None, true)
+                    .await;
+            }));
         }
     }
 
@@ -128,15 +616,12 @@ impl Api {
         let api = self.clone();
         let request = request.serialize();
         async move {
-            match timeout(
-                duration,
-                api.send_http_request::<Req::Response>(request.map_err(ErrorKind::from)?),
-            )
-                .await
-            {
-                Err(_) => Ok(None),
-                Ok(Ok(result)) => Ok(Some(result)),
-                Ok(Err(error)) => Err(error),
+            let request = request.map_err(ErrorKind::from)?;
+            let response = api.send_http_request::<Req::Response>(request, None, None, true);
+            match timeout(api.runtime(), duration, response).await {
+                None => Ok(None),
+                Some(Ok(result)) => Ok(Some(result)),
+                Some(Err(error)) => Err(error),
             }
         }
     }
@@ -165,41 +650,376 @@ impl Api {
         let api = self.clone();
         let request = request.serialize();
         async move {
-            api.send_http_request::<Req::Response>(request.map_err(ErrorKind::from)?)
-                .await
+            api.send_http_request::<Req::Response>(
+                request.map_err(ErrorKind::from)?,
+                api.0.timeout,
+                None,
+                true,
+            )
+            .await
+        }
+    }
+
+    /// Send a request to the Telegram server and wait for a response,
+    /// calling `progress` with the number of bytes sent so far and the total size
+    /// while files of the request are uploaded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, ChatId, InputFileUpload, prelude::*};
+    /// #
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let telegram_token = "token";
+    /// # let api = Api::new(telegram_token);
+    /// # if false {
+    /// let chat = ChatId::new(61031);
+    /// let file = InputFileUpload::with_path("video.mp4");
+    /// let result = api
+    ///     .send_with_progress(chat.document(file), |sent, total| {
+    ///         println!("uploaded {}%", sent * 100 / total)
+    ///     })
+    ///     .await;
+    /// println!("{:?}", result);
+    /// # }
+    /// # }
+    /// ```
+    pub fn send_with_progress<Req, F>(
+        &self,
+        request: Req,
+        progress: F,
+    ) -> impl Future<Output=Result<<Req::Response as ResponseType>::Type, Error>> + Send
+    where
+        Req: Request,
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        let api = self.clone();
+        let request = request.serialize();
+        let progress: UploadProgress = Arc::new(progress);
+        async move {
+            api.send_http_request::<Req::Response>(
+                request.map_err(ErrorKind::from)?,
+                api.0.timeout,
+                Some(progress),
+                true,
+            )
+            .await
         }
     }
 
+    /// Send requests to the Telegram server, at most `concurrency` at a time.
+    ///
+    /// Returns a stream of the results together with the indices of their requests.
+    /// If the flood control rejects a request, sending of all requests is paused
+    /// for the time requested by the server and the request is resent, at most 3 times.
+    /// The [`RetryPolicy`] of the `Api` doesn't apply to these requests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, ChatId, prelude::*};
+    /// # use futures::StreamExt;
+    /// #
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let telegram_token = "token";
+    /// # let api = Api::new(telegram_token);
+    /// # if false {
+    /// let chats = vec![ChatId::new(61031), ChatId::new(61032)];
+    /// let messages: Vec<_> = chats.iter().map(|chat| chat.text("Hello")).collect();
+    /// let mut results = api.send_all(messages, 4);
+    /// results.progress(|completed| println!("sent {} messages", completed));
+    ///
+    /// while let Some((index, result)) = results.next().await {
+    ///     println!("{:?}: {:?}", chats[index], result);
+    /// }
+    /// # }
+    /// # }
+    /// ```
+    ///
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    pub fn send_all<I, Req>(
+        &self,
+        requests: I,
+        concurrency: usize,
+    ) -> SendAll<<Req::Response as ResponseType>::Type>
+    where
+        I: IntoIterator<Item = Req>,
+        I::IntoIter: Send + 'static,
+        Req: Request + Send + 'static,
+        <Req::Response as ResponseType>::Type: Send + 'static,
+    {
+        SendAll::new(self, requests, concurrency)
+    }
+
+    /// Send the request without resending it on flood control errors.
+    pub(crate) async fn send_prepared<Resp: ResponseType>(
+        &self,
+        request: HttpRequest,
+    ) -> Result<Resp::Type, Error> {
+        self.send_http_request::<Resp>(request, self.0.timeout, None, false).await
+    }
+
     async fn send_http_request<Resp: ResponseType>(
         &self,
         request: HttpRequest,
+        attempt_timeout: Option<Duration>,
+        progress: Option<UploadProgress>,
+        retry: bool,
     ) -> Result<Resp::Type, Error> {
         let request_id = self.0.next_request_id.fetch_add(1, Ordering::Relaxed);
         let span = tracing::trace_span!("send_http_request", request_id = request_id);
+        let name = request.name();
+        let started = Instant::now();
         async {
-            tracing::trace!(name = %request.name(), body = %request.body, "sending request");
-            let http_response = self.0.connector.request(&self.0.token, request).await?;
-            tracing::trace!(
-                response = %match http_response.body {
-                    Some(ref vec) => match std::str::from_utf8(vec) {
-                        Ok(str) => str,
-                        Err(_) => "<invalid utf-8 string>"
-                    },
-                    None => "<empty body>",
-                }, "response received"
-            );
+            let mut request = request;
+            if let Some(ref store) = self.0.migrations {
+                migrate_request(&**store, &mut request);
+            }
+            if self.0.local_server {
+                use_local_files(&mut request)?;
+            }
+
+            let mut attempt = 1;
+            let mut waited = Duration::from_secs(0);
+            let mut migrated = false;
+            loop {
+                let delay = {
+                    let limited_chat = match self.0.rate_limiter {
+                        Some(ref limiter) => request.chat_ref().map(|chat| (limiter, chat)),
+                        None => None,
+                    };
+                    if let Some((limiter, chat)) = &limited_chat {
+                        limiter.acquire(chat, self.runtime()).await;
+                    }
+                    let response =
+                        self.execute_http_request::<Resp>(request.clone(), progress.clone());
+                    let result = match attempt_timeout {
+                        Some(duration) => match timeout(self.runtime(), duration, response).await {
+                            Some(result) => result,
+                            None => Err(ErrorKind::Timeout(duration).into()),
+                        },
+                        None => response.await,
+                    };
+                    if let Some((limiter, chat)) = &limited_chat {
+                        match result {
+                            Ok(_) => limiter.succeeded(chat),
+                            Err(ref error) => limiter.learn(chat, error),
+                        }
+                    }
+                    if let (Some(store), Err(error)) = (&self.0.migrations, &result) {
+                        if let Some(supergroup) = error.migrate_to_chat_id() {
+                            if !migrated
+                                && migrate_failed_request(&**store, &mut request, supergroup)
+                            {
+                                migrated = true;
+                                continue;
+                            }
+                        }
+                    }
+                    let delay = match (&result, &self.0.retry_policy) {
+                        (Err(error), Some(policy)) if retry => {
+                            policy.retry_delay(request.name(), attempt, waited, error)
+                        }
+                        _ => None,
+                    };
+                    match delay {
+                        Some(delay) => delay,
+                        None => return result,
+                    }
+                };
 
-            let response = Resp::deserialize(http_response).map_err(ErrorKind::from)?;
-            tracing::trace!("response deserialized");
-            Ok(response)
+                tracing::warn!(
+                    name = %request.name(),
+                    attempt = attempt,
+                    delay = ?delay,
+                    "flood control exceeded, retrying request"
+                );
+                self.runtime().sleep(delay).await;
+                attempt += 1;
+                waited += delay;
+            }
         }
             .map(|result| {
                 if let Err(ref error) = result {
                     tracing::error!(error = %error);
                 }
+                let outcome = result.as_ref().map(|_| ());
+                self.0.metrics.request(name, started.elapsed(), outcome);
                 result
             })
             .instrument(span)
             .await
     }
+
+    async fn execute_http_request<Resp: ResponseType>(
+        &self,
+        request: HttpRequest,
+        progress: Option<UploadProgress>,
+    ) -> Result<Resp::Type, Error> {
+        tracing::trace!(name = %request.name(), body = %request.body, "sending request");
+        let connector = &self.0.connector;
+        let http_response = match progress {
+            Some(progress) => connector.upload(&self.0.api_url, &self.0.token, request, progress),
+            None => connector.request(&self.0.api_url, &self.0.token, request),
+        }
+        .await?;
+        tracing::trace!(
+            response = %match http_response.body {
+                Some(ref vec) => match std::str::from_utf8(vec) {
+                    Ok(str) => str,
+                    Err(_) => "<invalid utf-8 string>"
+                },
+                None => "<empty body>",
+            }, "response received"
+        );
+
+        let response = Resp::deserialize(http_response).map_err(ErrorKind::from)?;
+        tracing::trace!("response deserialized");
+        Ok(response)
+    }
+}
+
+/// Characters which are escaped in paths of `file://` URIs.
+const FILE_URI_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Pass files to the local Bot API server by their `file://` URI instead of uploading them.
+fn use_local_files(request: &mut HttpRequest) -> Result<(), Error> {
+    if let Body::Multipart(ref mut parts) = request.body {
+        for (_, value) in parts.iter_mut() {
+            let uri = match value {
+                MultipartValue::Path { path, .. } => {
+                    let path = Path::new(path.as_str());
+                    let path = if path.is_absolute() {
+                        path.to_path_buf()
+                    } else {
+                        env::current_dir().map_err(ErrorKind::from)?.join(path)
+                    };
+                    let path = path.to_string_lossy();
+                    format!("file://{}", utf8_percent_encode(&path, FILE_URI_ENCODE_SET))
+                }
+                _ => continue,
+            };
+            *value = MultipartValue::Text(uri.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{ErrorCategory, HttpResponse};
+
+    use crate::test_support::replay_api;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_download_file_to() {
+        let api = replay_api(&[("downloadFile", "contents")]);
+        let mut file = File {
+            file_id: "file_id".into(),
+            file_size: None,
+            file_path: Some("documents/file.txt".into()),
+        };
+
+        let mut contents = Vec::new();
+        let size = api.download_file_to(&file, &mut contents, None).await;
+        assert_eq!(size.unwrap(), 8);
+        assert_eq!(contents, b"contents");
+
+        let mut contents = Vec::new();
+        let result = api.download_file_to(&file, &mut contents, Some(4)).await;
+        assert!(result.is_err());
+        assert!(contents.is_empty());
+
+        let path = env::temp_dir().join(format!("telegram-bot-download-{}", std::process::id()));
+        let size = api.download_file_to_path(&file, &path, None).await;
+        assert_eq!(size.unwrap(), 8);
+        assert_eq!(std::fs::read(&path).unwrap(), b"contents");
+        let result = api.download_file_to_path(&file, &path, Some(4)).await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+
+        file.file_path = None;
+        assert!(api.download_file_to(&file, &mut contents, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_timeout() {
+        #[derive(Debug)]
+        struct Stalled;
+
+        impl Connector for Stalled {
+            fn request(
+                &self,
+                _api_url: &ApiUrl,
+                _token: &BotToken,
+                _req: HttpRequest,
+            ) -> std::pin::Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>
+            {
+                future::pending().boxed()
+            }
+        }
+
+        let api = Api::builder("token")
+            .connector(Box::new(Stalled))
+            .timeout(Duration::from_millis(10))
+            .build();
+        let file = File {
+            file_id: "file_id".into(),
+            file_size: None,
+            file_path: Some("documents/file.txt".into()),
+        };
+        let error = api.download_file(&file).next().await.unwrap().unwrap_err();
+        assert_eq!(error.category(), ErrorCategory::Timeout);
+    }
+
+    #[test]
+    fn test_use_local_files() {
+        let mut request = HttpRequest {
+            url: RequestUrl::method("sendDocument"),
+            method: Method::Post,
+            body: Body::Multipart(vec![
+                ("chat_id", MultipartValue::Text("42".into())),
+                (
+                    "document",
+                    MultipartValue::Path {
+                        path: "/tmp/file.txt".into(),
+                        file_name: None,
+                    },
+                ),
+                (
+                    "thumb",
+                    MultipartValue::Path {
+                        path: "/tmp/my #1 100%.jpg".into(),
+                        file_name: None,
+                    },
+                ),
+            ]),
+        };
+        use_local_files(&mut request).unwrap();
+        match request.body {
+            Body::Multipart(parts) => {
+                assert_eq!(parts[0].1, MultipartValue::Text("42".into()));
+                assert_eq!(parts[1].1, MultipartValue::Text("file:///tmp/file.txt".into()));
+                assert_eq!(
+                    parts[2].1,
+                    MultipartValue::Text("file:///tmp/my%20%231%20100%25.jpg".into())
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
 }
@@ -0,0 +1,35 @@
+//! Files which are replaced atomically, used by the file based stores.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Replace the file at `path` with `contents`.
+///
+/// The contents are written to a temporary file next to `path`, synced to disk and
+/// renamed over `path`, so a crash leaves either the old or the new file behind.
+pub(crate) fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    // Append to the whole file name, so files differing only by extension don't share it.
+    let mut temporary = path.file_name().unwrap_or_default().to_os_string();
+    temporary.push(".tmp");
+    let temporary = path.with_file_name(temporary);
+    let mut file = File::create(&temporary)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+    sync_parent(path)
+}
+
+/// Sync the directory containing `path`, so a rename into it survives a crash.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Synchronous facade over `Api` for code which doesn't run in an async context.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Future, StreamExt};
+use tokio::runtime::{Builder, Runtime};
+
+use telegram_bot_raw::{BotToken, Request, ResponseType, Update};
+
+use crate::api::Api;
+use crate::errors::{Error, ErrorKind};
+use crate::stream::UpdatesStream;
+
+/// Blocking version of [`Api`], which owns a runtime to drive requests to completion.
+///
+/// The runtime runs on its own worker threads, so clones of a `BlockingApi` can send
+/// requests from many threads at once, even while another thread waits for updates.
+/// Must not be used from within an async context.
+///
+/// # Example
+///
+/// ```rust
+/// use telegram_bot::GetMe;
+/// use telegram_bot::blocking::BlockingApi;
+///
+/// # fn main() {
+/// # let telegram_token = "token";
+/// let api = BlockingApi::new(telegram_token).unwrap();
+/// # if false {
+/// println!("{:?}", api.send(GetMe));
+///
+/// for update in api.updates() {
+///     println!("{:?}", update);
+/// }
+/// # }
+/// # }
+/// ```
+///
+/// [`Api`]: ../struct.Api.html
+#[derive(Clone)]
+pub struct BlockingApi {
+    api: Api,
+    runtime: Arc<Runtime>,
+}
+
+impl BlockingApi {
+    /// Create a new `BlockingApi` instance with the default configuration.
+    pub fn new<T: Into<BotToken>>(token: T) -> Result<Self, Error> {
+        Self::from_api(Api::new(token))
+    }
+
+    /// Create a new `BlockingApi` instance sending requests through `api`.
+    pub fn from_api(api: Api) -> Result<Self, Error> {
+        let runtime = Builder::new()
+            .threaded_scheduler()
+            .core_threads(1)
+            .thread_name("telegram-bot-blocking")
+            .enable_all()
+            .build()
+            .map_err(ErrorKind::from)?;
+
+        Ok(BlockingApi {
+            api,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns the underlying async `Api` instance.
+    pub fn api(&self) -> &Api {
+        &self.api
+    }
+
+    /// Send a request to the Telegram server and wait for a response.
+    pub fn send<Req: Request>(
+        &self,
+        request: Req,
+    ) -> Result<<Req::Response as ResponseType>::Type, Error> {
+        self.block_on(self.api.send(request))
+    }
+
+    /// Send a request to the Telegram server and wait for a response, timing out after `duration`.
+    pub fn send_timeout<Req: Request>(
+        &self,
+        request: Req,
+        duration: Duration,
+    ) -> Result<Option<<Req::Response as ResponseType>::Type>, Error> {
+        self.block_on(self.api.send_timeout(request, duration))
+    }
+
+    /// Returns an iterator over the updates received by long polling.
+    pub fn updates(&self) -> Updates {
+        self.updates_from(self.api.stream())
+    }
+
+    /// Returns an iterator over the updates produced by a configured `stream`.
+    pub fn updates_from(&self, stream: UpdatesStream) -> Updates {
+        Updates {
+            stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.handle().block_on(future)
+    }
+}
+
+/// Blocking iterator over updates, the equivalent of [`UpdatesStream`].
+///
+/// [`UpdatesStream`]: ../struct.UpdatesStream.html
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Updates {
+    stream: UpdatesStream,
+    runtime: Arc<Runtime>,
+}
+
+impl Updates {
+    /// Returns the underlying stream, e.g. to change its configuration.
+    pub fn stream_mut(&mut self) -> &mut UpdatesStream {
+        &mut self.stream
+    }
+}
+
+impl Iterator for Updates {
+    type Item = Result<Update, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.handle().block_on(self.stream.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Instant;
+
+    use telegram_bot_raw::{ChatId, DeleteMessage, MessageId};
+
+    use super::*;
+    use crate::test_support::replay_api;
+
+    #[test]
+    fn test_blocking_send() {
+        let api = replay_api(&[("deleteMessage", r#"{"ok":true,"result":true}"#)]);
+        let api = BlockingApi::from_api(api).unwrap();
+
+        let request = DeleteMessage::new(ChatId::new(1), MessageId::new(1));
+        assert!(api.send(request.clone()).is_ok());
+        let response = api.send_timeout(request, Duration::from_secs(1));
+        assert_eq!(response.unwrap(), Some(()));
+    }
+
+    #[test]
+    fn test_blocking_concurrent() {
+        let api = replay_api(&[("deleteMessage", r#"{"ok":true,"result":true}"#)]);
+        let api = BlockingApi::from_api(api).unwrap();
+
+        let waiting = api.clone();
+        thread::spawn(move || waiting.block_on(tokio::time::delay_for(Duration::from_secs(5))));
+        thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        let request = DeleteMessage::new(ChatId::new(1), MessageId::new(1));
+        assert!(api.send(request).is_ok());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
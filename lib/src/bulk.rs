@@ -0,0 +1,286 @@
+use std::cmp::max;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::{future, stream, Stream, StreamExt};
+
+use telegram_bot_raw::{HttpRequest, Request, ResponseType};
+
+use crate::api::Api;
+use crate::errors::{Error, ErrorKind};
+use crate::runtime::Runtime;
+
+const MAX_FLOOD_WAIT_ATTEMPTS: usize = 3;
+
+type ProgressCallback = Arc<dyn Fn(usize) + Send + Sync>;
+type Results<T> = Pin<Box<dyn Stream<Item = (usize, Result<T, Error>)> + Send>>;
+
+/// Stream of the results of requests sent by [`Api::send_all`].
+///
+/// Every result is yielded together with the index of its request,
+/// in the order the requests are completed.
+///
+/// When a request is rejected by the flood control, sending of all requests
+/// is paused for the time requested by the server and the request is resent,
+/// at most 3 times. The [`RetryPolicy`] of the `Api` doesn't apply on top of that.
+///
+/// [`Api::send_all`]: struct.Api.html#method.send_all
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+#[must_use = "streams do nothing unless polled"]
+pub struct SendAll<T> {
+    inner: Results<T>,
+    shared: Arc<Shared>,
+}
+
+/// Handle stopping a [`SendAll`] stream.
+///
+/// [`SendAll`]: struct.SendAll.html
+#[derive(Debug, Clone)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+struct Shared {
+    cancelled: Arc<AtomicBool>,
+    paused_until: Mutex<Option<Instant>>,
+    completed: AtomicUsize,
+    progress: Mutex<Option<ProgressCallback>>,
+}
+
+impl<T: Send + 'static> SendAll<T> {
+    pub(crate) fn new<I, Req>(api: &Api, requests: I, concurrency: usize) -> Self
+    where
+        I: IntoIterator<Item = Req>,
+        I::IntoIter: Send + 'static,
+        Req: Request + Send + 'static,
+        Req::Response: ResponseType<Type = T>,
+    {
+        let shared = Arc::new(Shared {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            paused_until: Mutex::new(None),
+            completed: AtomicUsize::new(0),
+            progress: Mutex::new(None),
+        });
+
+        let api = api.clone();
+        let sending = shared.clone();
+        let reporting = shared.clone();
+        let inner = stream::iter(requests.into_iter().enumerate())
+            .take_while({
+                let shared = shared.clone();
+                move |_| future::ready(!shared.is_cancelled())
+            })
+            .map(move |(index, request)| {
+                let api = api.clone();
+                let shared = sending.clone();
+                let request = request.serialize();
+                async move {
+                    let result = match request {
+                        Ok(request) => send::<Req::Response>(&api, &shared, request).await?,
+                        Err(err) => Err(ErrorKind::from(err).into()),
+                    };
+                    Some((index, result))
+                }
+            })
+            .buffer_unordered(max(concurrency, 1))
+            .filter_map(future::ready)
+            .inspect(move |_| reporting.report_progress());
+
+        SendAll {
+            inner: Box::pin(inner),
+            shared,
+        }
+    }
+}
+
+impl<T> SendAll<T> {
+    /// Call `callback` with the number of completed requests after every request.
+    pub fn progress<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        *self.shared.progress.lock().unwrap() = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns a handle stopping the stream: requests which are already being sent
+    /// are still completed and yielded, the remaining requests are not sent.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle(self.shared.cancelled.clone())
+    }
+}
+
+impl<T> Stream for SendAll<T> {
+    type Item = (usize, Result<T, Error>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl CancelHandle {
+    /// Stop sending the remaining requests.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the stream has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Shared {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Pause sending of all requests for `duration`.
+    fn pause(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        match *paused_until {
+            Some(paused_until) if paused_until >= until => {}
+            _ => {
+                tracing::warn!(duration = ?duration, "flood control exceeded, pausing bulk send");
+                *paused_until = Some(until);
+            }
+        }
+    }
+
+    /// Wait until the pause is over, waits again if it was extended in the meantime.
+    async fn wait(&self, runtime: &dyn Runtime) {
+        loop {
+            let paused_until = *self.paused_until.lock().unwrap();
+            let now = Instant::now();
+            match paused_until {
+                Some(until) if until > now => {
+                    runtime.sleep(until - now).await;
+                    let mut paused_until = self.paused_until.lock().unwrap();
+                    if *paused_until == Some(until) {
+                        *paused_until = None;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn report_progress(&self) {
+        let completed = self.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let progress = self.progress.lock().unwrap().clone();
+        if let Some(progress) = progress {
+            progress(completed);
+        }
+    }
+}
+
+/// Send the request, resending it after a pause if it was rejected by the flood control.
+/// Returns `None` if the stream was cancelled before the request was sent,
+/// or the flood control error if it was cancelled while waiting to resend it.
+async fn send<Resp: ResponseType>(
+    api: &Api,
+    shared: &Shared,
+    request: HttpRequest,
+) -> Option<Result<Resp::Type, Error>> {
+    let mut attempt = 1;
+    let mut rejected = None;
+    loop {
+        shared.wait(api.runtime()).await;
+        if shared.is_cancelled() {
+            return rejected.map(Err);
+        }
+
+        match api.send_prepared::<Resp>(request.clone()).await {
+            Err(error) if attempt < MAX_FLOOD_WAIT_ATTEMPTS => match error.retry_after() {
+                Some(retry_after) => {
+                    shared.pause(retry_after);
+                    rejected = Some(error);
+                }
+                None => return Some(Err(error)),
+            },
+            result => return Some(result),
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use telegram_bot_raw::{ChatId, DeleteMessage, MessageId};
+
+    use super::*;
+    use crate::connector::cassette::ReplayConnector;
+    use crate::test_support::{interaction, Sleeps};
+
+    fn api(connector: ReplayConnector, sleeps: &Arc<Sleeps>) -> Api {
+        Api::builder("token")
+            .connector(Box::new(connector.strict(true)))
+            .runtime(sleeps.clone())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_send_all_pauses_on_flood_wait() {
+        let request = |chat| DeleteMessage::new(ChatId::new(chat), MessageId::new(1));
+        let interaction = |chat, response| {
+            let body = request(chat).serialize().unwrap().body.to_string();
+            interaction("deleteMessage", &body, response)
+        };
+        let flood = r#"{"ok":false,"error_code":429,"description":"Too Many Requests",
+            "parameters":{"retry_after":1}}"#;
+        let ok = r#"{"ok":true,"result":true}"#;
+        let connector = ReplayConnector::new(vec![
+            interaction(1, flood),
+            interaction(1, ok),
+            interaction(2, ok),
+        ]);
+        let sleeps = Arc::new(Sleeps::new());
+        let api = api(connector, &sleeps);
+
+        let completed = Arc::new(AtomicUsize::new(0));
+        let reported = completed.clone();
+        let mut results = api.send_all(vec![request(1), request(2)], 1);
+        results.progress(move |count| reported.store(count, Ordering::SeqCst));
+        let results = results.collect::<Vec<_>>().await;
+
+        // Sending is paused once for the time requested by the server.
+        let slept = sleeps.slept();
+        assert_eq!(slept.len(), 1);
+        assert!(slept[0] > Duration::from_millis(900) && slept[0] <= Duration::from_secs(1));
+        assert_eq!(
+            results.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            [0, 1]
+        );
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(completed.load(Ordering::SeqCst), 2);
+
+        let mut results = api.send_all(vec![request(2)], 1);
+        results.cancel_handle().cancel();
+        assert!(results.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_during_flood_wait() {
+        let request = DeleteMessage::new(ChatId::new(1), MessageId::new(1));
+        let body = request.serialize().unwrap().body.to_string();
+        let flood = r#"{"ok":false,"error_code":429,"description":"Too Many Requests",
+            "parameters":{"retry_after":1}}"#;
+        let connector = ReplayConnector::new(vec![interaction("deleteMessage", &body, flood)]);
+        let sleeps = Arc::new(Sleeps::new());
+        let api = api(connector, &sleeps);
+
+        let mut results = api.send_all(vec![request], 1);
+        let cancel = results.cancel_handle();
+        sleeps.on_sleep(move || cancel.cancel());
+
+        // The flood control error is yielded instead of silently dropping the request.
+        let (index, result) = results.next().await.unwrap();
+        assert_eq!(index, 0);
+        assert!(result.unwrap_err().retry_after().is_some());
+        assert!(results.next().await.is_none());
+    }
+}
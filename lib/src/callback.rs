@@ -0,0 +1,244 @@
+use std::error;
+use std::fmt;
+
+use hmac::{Hmac, Mac, NewMac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::Sha256;
+
+use telegram_bot_raw::{check_callback_data, CallbackQuery, InlineKeyboardButton};
+
+/// Length of the truncated HMAC in bytes, encoded as 11 base64 characters.
+const SIGNATURE_LEN: usize = 8;
+const ENCODED_SIGNATURE_LEN: usize = 11;
+
+/// Encodes serde types into callback data of inline keyboard buttons and decodes
+/// the data of callback queries.
+///
+/// Values are encoded as JSON. With a [key](#method.with_key) the data is signed with
+/// a truncated HMAC-SHA256, so payloads forged by clients are rejected.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use telegram_bot::CallbackCodec;
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// enum Action {
+///     Vote { poll: u32, option: u8 },
+///     Cancel,
+/// }
+///
+/// let codec = CallbackCodec::with_key("secret");
+/// let data = codec.encode(&Action::Vote { poll: 7, option: 2 }).unwrap();
+/// assert_eq!(codec.variant(&data).unwrap(), "Vote");
+/// assert_eq!(codec.decode::<Action>(&data).unwrap(), Action::Vote { poll: 7, option: 2 });
+///
+/// let button = codec.button("Cancel", &Action::Cancel).unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct CallbackCodec {
+    key: Option<Vec<u8>>,
+}
+
+/// Error of encoding or decoding callback data.
+#[derive(Debug)]
+pub enum CallbackDataError {
+    /// The encoded data is rejected as callback data, because it's longer than 64 bytes.
+    Invalid(telegram_bot_raw::Error),
+    /// The callback query has no data.
+    Missing,
+    /// The signature of the data is missing or wrong.
+    InvalidSignature,
+    /// The data isn't an enum variant.
+    NotAnEnum,
+    /// The data couldn't be serialized or deserialized.
+    Json(serde_json::Error),
+}
+
+impl CallbackCodec {
+    /// Create a new `CallbackCodec` without signatures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new `CallbackCodec` signing the data with `key`.
+    pub fn with_key<K: AsRef<[u8]>>(key: K) -> Self {
+        CallbackCodec {
+            key: Some(key.as_ref().to_vec()),
+        }
+    }
+
+    /// Encode `value` into callback data, fails if the data is longer than 64 bytes.
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<String, CallbackDataError> {
+        let json = serde_json::to_string(value).map_err(CallbackDataError::Json)?;
+        let data = match self.signature(&json) {
+            Some(signature) => signature + &json,
+            None => json,
+        };
+        check_callback_data(&data).map_err(CallbackDataError::Invalid)?;
+        Ok(data)
+    }
+
+    /// Decode callback data into a `T`.
+    pub fn decode<T: DeserializeOwned>(&self, data: &str) -> Result<T, CallbackDataError> {
+        serde_json::from_str(self.verify(data)?).map_err(CallbackDataError::Json)
+    }
+
+    /// Decode the data of a callback query into a `T`.
+    pub fn decode_query<T: DeserializeOwned>(
+        &self,
+        query: &CallbackQuery,
+    ) -> Result<T, CallbackDataError> {
+        match query.data {
+            Some(ref data) => self.decode(data),
+            None => Err(CallbackDataError::Missing),
+        }
+    }
+
+    /// Returns the name of the enum variant encoded in the data.
+    pub fn variant(&self, data: &str) -> Result<String, CallbackDataError> {
+        self.decode_variant(data).map(|(variant, _)| variant)
+    }
+
+    /// Returns the name of the enum variant encoded in the data
+    /// together with the decoded JSON, so it can be deserialized without verifying it again.
+    pub(crate) fn decode_variant(
+        &self,
+        data: &str,
+    ) -> Result<(String, serde_json::Value), CallbackDataError> {
+        let value: serde_json::Value =
+            serde_json::from_str(self.verify(data)?).map_err(CallbackDataError::Json)?;
+        let variant = match value {
+            serde_json::Value::String(ref variant) => variant.clone(),
+            serde_json::Value::Object(ref map) if map.len() == 1 => match map.keys().next() {
+                Some(variant) => variant.clone(),
+                None => return Err(CallbackDataError::NotAnEnum),
+            },
+            _ => return Err(CallbackDataError::NotAnEnum),
+        };
+        Ok((variant, value))
+    }
+
+    /// Create an inline keyboard button sending `value` as callback data.
+    pub fn button<S, T>(
+        &self,
+        text: S,
+        value: &T,
+    ) -> Result<InlineKeyboardButton, CallbackDataError>
+    where
+        S: AsRef<str>,
+        T: Serialize,
+    {
+        InlineKeyboardButton::try_callback(text, self.encode(value)?)
+            .map_err(CallbackDataError::Invalid)
+    }
+
+    /// Returns the JSON part of `data` after checking its signature.
+    fn verify<'a>(&self, data: &'a str) -> Result<&'a str, CallbackDataError> {
+        if self.key.is_none() {
+            return Ok(data);
+        }
+        if data.len() < ENCODED_SIGNATURE_LEN || !data.is_char_boundary(ENCODED_SIGNATURE_LEN) {
+            return Err(CallbackDataError::InvalidSignature);
+        }
+        let (signature, json) = data.split_at(ENCODED_SIGNATURE_LEN);
+        let expected = self.signature(json).unwrap_or_default();
+        // Compare in constant time to not leak the expected signature.
+        let difference = expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        if difference != 0 {
+            return Err(CallbackDataError::InvalidSignature);
+        }
+        Ok(json)
+    }
+
+    fn signature(&self, json: &str) -> Option<String> {
+        let key = self.key.as_ref()?;
+        let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+        mac.update(json.as_bytes());
+        let tag = mac.finalize().into_bytes();
+        Some(base64::encode_config(
+            &tag[..SIGNATURE_LEN],
+            base64::URL_SAFE_NO_PAD,
+        ))
+    }
+}
+
+impl fmt::Debug for CallbackCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallbackCodec")
+            .field("signed", &self.key.is_some())
+            .finish()
+    }
+}
+
+impl fmt::Display for CallbackDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallbackDataError::Invalid(error) => write!(f, "{}", error),
+            CallbackDataError::Missing => write!(f, "callback query has no data"),
+            CallbackDataError::InvalidSignature => write!(f, "invalid callback data signature"),
+            CallbackDataError::NotAnEnum => write!(f, "callback data isn't an enum variant"),
+            CallbackDataError::Json(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for CallbackDataError {}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Action {
+        Vote(u32),
+        Comment(String),
+    }
+
+    #[test]
+    fn test_callback_codec() {
+        let codec = CallbackCodec::with_key("secret");
+        let data = codec.encode(&Action::Vote(3)).unwrap();
+        assert_eq!(data.len(), ENCODED_SIGNATURE_LEN + r#"{"Vote":3}"#.len());
+        assert_eq!(codec.decode::<Action>(&data).unwrap(), Action::Vote(3));
+
+        let forged = data.replace('3', "4");
+        match codec.decode::<Action>(&forged) {
+            Err(CallbackDataError::InvalidSignature) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        match CallbackCodec::with_key("other").decode::<Action>(&data) {
+            Err(CallbackDataError::InvalidSignature) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        match codec.encode(&Action::Comment("x".repeat(40))) {
+            Err(CallbackDataError::Invalid(error)) => assert_eq!(
+                error.to_string(),
+                "callback data is 65 bytes long, the limit is 64 bytes"
+            ),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let unsigned = CallbackCodec::new().encode(&Action::Comment("x".repeat(40)));
+        assert_eq!(unsigned.unwrap().len(), 54);
+    }
+
+    #[test]
+    fn test_variant() {
+        let codec = CallbackCodec::new();
+        let data = codec.encode(&Action::Vote(3)).unwrap();
+        assert_eq!(codec.variant(&data).unwrap(), "Vote");
+        assert_eq!(codec.variant(r#""Cancel""#).unwrap(), "Cancel");
+
+        for data in &["3", r#"{"a":1,"b":2}"#, "[]"] {
+            match codec.variant(data) {
+                Err(CallbackDataError::NotAnEnum) => (),
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Parsing bot commands into typed enums.
+//!
+//! Derive [`BotCommands`] on an enum with the `macros` feature (enabled by default):
+//!
+//! ```rust
+//! # #[cfg(feature = "macros")]
+//! # fn main() {
+//! use telegram_bot::BotCommands;
+//!
+//! #[derive(BotCommands, Debug, PartialEq)]
+//! #[command(rename_all = "snake_case", case_insensitive)]
+//! enum Command {
+//!     /// Start the bot
+//!     #[command(alias = "hello")]
+//!     Start,
+//!     /// Ban a user for some days
+//!     Ban { user: i64, days: u32 },
+//!     /// Repeat a text
+//!     Echo(String),
+//!     #[command(hide)]
+//!     Debug,
+//! }
+//!
+//! assert_eq!(Command::parse("/hello@my_bot", "my_bot"), Ok(Command::Start));
+//! assert_eq!(
+//!     Command::parse("/ban 42 7", "my_bot"),
+//!     Ok(Command::Ban { user: 42, days: 7 })
+//! );
+//! assert_eq!(
+//!     Command::parse("/ECHO hello world", "my_bot"),
+//!     Ok(Command::Echo("hello world".into()))
+//! );
+//! assert!(Command::parse("/start@other_bot", "my_bot").is_err());
+//! assert_eq!(
+//!     Command::help(),
+//!     "/start - Start the bot\n/ban <user> <days> - Ban a user for some days\n/echo <String> - Repeat a text"
+//! );
+//! # }
+//! # #[cfg(not(feature = "macros"))]
+//! # fn main() {}
+//! ```
+//!
+//! Variants are matched by their name in lowercase, or in snake case with
+//! `#[command(rename_all = "snake_case")]`. `case_insensitive` matches commands regardless
+//! of their case. Variants accept `#[command(rename = "..")]`, any number of
+//! `#[command(alias = "..")]`, `#[command(description = "..")]` overriding the doc comment
+//! and `#[command(hide)]` excluding them from the help text and the commands list.
+//!
+//! Fields are parsed from whitespace separated arguments with `FromStr`, a trailing `String`
+//! field receives the rest of the text.
+//!
+//! The derive fails to compile when a name or alias isn't 1 to 32 lowercase letters,
+//! digits and underscores, when two variants share a name or alias, or when a visible
+//! command has no description of 3 to 256 characters, as Telegram would reject them.
+//! Options placed on the wrong item, like `alias` on the enum, are rejected as well.
+//!
+//! ```compile_fail
+//! # use telegram_bot::BotCommands;
+//! #[derive(BotCommands)]
+//! enum Command {
+//!     /// Start the bot
+//!     #[command(rename = "Start-Bot")]
+//!     Start,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use telegram_bot::BotCommands;
+//! #[derive(BotCommands)]
+//! enum Command {
+//!     /// Start the bot
+//!     Start,
+//!     /// Start the bot too
+//!     #[command(alias = "start")]
+//!     Begin,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use telegram_bot::BotCommands;
+//! #[derive(BotCommands)]
+//! enum Command {
+//!     Start,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use telegram_bot::BotCommands;
+//! #[derive(BotCommands)]
+//! #[command(alias = "begin")]
+//! enum Command {
+//!     /// Start the bot
+//!     Start,
+//! }
+//! ```
+//!
+//! [`BotCommands`]: trait.BotCommands.html
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use telegram_bot_raw::{BotCommand, Message, MessageKind, SetMyCommands, User};
+
+/// Enum of bot commands, usually derived with `#[derive(BotCommands)]`.
+pub trait BotCommands: Sized {
+    /// Parse the command `text`, `username` is the username of the bot.
+    ///
+    /// Commands addressed to other bots, like `/start@other_bot`, are rejected.
+    fn parse(text: &str, username: &str) -> Result<Self, ParseCommandError>;
+
+    /// Returns the visible commands with their descriptions.
+    fn bot_commands() -> Vec<BotCommand>;
+
+    /// Returns a help text listing the visible commands with their arguments and descriptions.
+    fn help() -> String;
+
+    /// Parse the command in `message`, `me` is the bot as returned by `GetMe`.
+    fn from_message(message: &Message, me: &User) -> Result<Self, ParseCommandError> {
+        match message.kind {
+            MessageKind::Text { ref data, .. } => {
+                Self::parse(data, me.username.as_ref().map_or("", String::as_str))
+            }
+            _ => Err(ParseCommandError::NotACommand),
+        }
+    }
+
+    /// Returns a request registering the visible commands with Telegram.
+    fn set_my_commands() -> SetMyCommands {
+        SetMyCommands::new(Self::bot_commands())
+    }
+}
+
+/// Error of parsing a bot command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCommandError {
+    /// The text doesn't start with a command.
+    NotACommand,
+    /// The command is addressed to another bot.
+    OtherBot(String),
+    /// The command is unknown.
+    UnknownCommand(String),
+    /// An argument of the command is missing.
+    MissingArgument {
+        command: String,
+        argument: &'static str,
+    },
+    /// An argument of the command couldn't be parsed.
+    InvalidArgument {
+        command: String,
+        argument: &'static str,
+        message: String,
+    },
+    /// The command got more arguments than it takes.
+    TooManyArguments { command: String },
+}
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCommandError::NotACommand => write!(f, "not a command"),
+            ParseCommandError::OtherBot(bot) => write!(f, "command is addressed to @{}", bot),
+            ParseCommandError::UnknownCommand(command) => write!(f, "unknown command /{}", command),
+            ParseCommandError::MissingArgument { command, argument } => {
+                write!(f, "/{} is missing the argument {}", command, argument)
+            }
+            ParseCommandError::InvalidArgument {
+                command,
+                argument,
+                message,
+            } => write!(
+                f,
+                "invalid argument {} of /{}: {}",
+                argument, command, message
+            ),
+            ParseCommandError::TooManyArguments { command } => {
+                write!(f, "too many arguments for /{}", command)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseCommandError {}
+
+/// Split `text` into the command name and its arguments.
+#[doc(hidden)]
+pub fn split_command<'a>(
+    text: &'a str,
+    username: &str,
+) -> Result<(&'a str, Arguments<'a>), ParseCommandError> {
+    if !text.starts_with('/') {
+        return Err(ParseCommandError::NotACommand);
+    }
+    let text = &text[1..];
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let (head, rest) = text.split_at(end);
+
+    let mut parts = head.splitn(2, '@');
+    let command = parts.next().unwrap_or("");
+    if let Some(bot) = parts.next() {
+        if !bot.eq_ignore_ascii_case(username) {
+            return Err(ParseCommandError::OtherBot(bot.to_string()));
+        }
+    }
+    if command.is_empty() {
+        return Err(ParseCommandError::NotACommand);
+    }
+    Ok((command, Arguments { command, rest }))
+}
+
+/// Arguments of a command, used by `#[derive(BotCommands)]`.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Arguments<'a> {
+    command: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Arguments<'a> {
+    /// Parse the next argument.
+    pub fn next<T>(&mut self, argument: &'static str) -> Result<T, ParseCommandError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let rest = self.rest.trim_start();
+        if rest.is_empty() {
+            return Err(self.missing(argument));
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (value, rest) = rest.split_at(end);
+        self.rest = rest;
+        value
+            .parse()
+            .map_err(|err: T::Err| ParseCommandError::InvalidArgument {
+                command: self.command.to_string(),
+                argument,
+                message: err.to_string(),
+            })
+    }
+
+    /// Returns the rest of the text.
+    pub fn rest(&mut self, argument: &'static str) -> Result<String, ParseCommandError> {
+        let rest = self.rest.trim();
+        self.rest = "";
+        if rest.is_empty() {
+            return Err(self.missing(argument));
+        }
+        Ok(rest.to_string())
+    }
+
+    /// Make sure all arguments were consumed.
+    pub fn finish(self) -> Result<(), ParseCommandError> {
+        if self.rest.trim().is_empty() {
+            Ok(())
+        } else {
+            Err(ParseCommandError::TooManyArguments {
+                command: self.command.to_string(),
+            })
+        }
+    }
+
+    fn missing(&self, argument: &'static str) -> ParseCommandError {
+        ParseCommandError::MissingArgument {
+            command: self.command.to_string(),
+            argument,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        let (command, mut arguments) = split_command("/ban@Bot 42  spam me", "bot").unwrap();
+        assert_eq!(command, "ban");
+        assert_eq!(arguments.next::<i64>("user"), Ok(42));
+        assert_eq!(arguments.rest("reason").unwrap(), "spam me");
+        assert_eq!(arguments.finish(), Ok(()));
+
+        let (_, mut arguments) = split_command("/ban x", "bot").unwrap();
+        assert!(match arguments.next::<i64>("user") {
+            Err(ParseCommandError::InvalidArgument { argument, .. }) => argument == "user",
+            _ => false,
+        });
+        let (_, arguments) = split_command("/start now", "bot").unwrap();
+        assert!(arguments.finish().is_err());
+
+        assert_eq!(
+            split_command("/start@other", "bot").unwrap_err(),
+            ParseCommandError::OtherBot("other".into())
+        );
+        assert_eq!(
+            split_command("start", "bot").unwrap_err(),
+            ParseCommandError::NotACommand
+        );
+    }
+}
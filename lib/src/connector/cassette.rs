@@ -0,0 +1,301 @@
+//! Connectors recording Bot API traffic into a cassette file and replaying it back,
+//! which allows testing bots offline and deterministically.
+//!
+//! A cassette is a JSONL file, every line is an [`Interaction`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use telegram_bot::Api;
+//! use telegram_bot::connector::cassette::{RecordLayer, ReplayConnector};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! // Record real traffic.
+//! let api = Api::builder("token")
+//!     .layer(RecordLayer::new("bot.cassette.jsonl")?)
+//!     .build();
+//!
+//! // Replay it in tests.
+//! let replay = ReplayConnector::from_file("bot.cassette.jsonl")?.strict(true);
+//! let api = Api::with_connector("token", Box::new(replay));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Interaction`]: struct.Interaction.html
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::{future, Future, FutureExt};
+use serde::{Deserialize, Serialize};
+use telegram_bot_raw::{ApiUrl, BotToken, HttpRequest, HttpResponse};
+
+use super::{ByteStream, Connector, Layer, UploadProgress};
+use crate::errors::{Error, ErrorKind};
+
+const REDACTED_TOKEN: &str = "<token>";
+
+/// Single request and the response to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Bot API method name.
+    pub method: String,
+    /// Request URL with the token redacted.
+    pub url: String,
+    /// Request body.
+    pub body: String,
+    /// Response body, if any.
+    pub response: Option<String>,
+}
+
+impl Interaction {
+    fn matches(&self, request: &HttpRequest) -> bool {
+        self.matches_method(request) && self.body == request.body.to_string()
+    }
+
+    fn matches_method(&self, request: &HttpRequest) -> bool {
+        self.method == request.name()
+    }
+}
+
+/// Layer which appends every successful request to a cassette file,
+/// file downloads are not recorded.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+    file: Arc<Mutex<File>>,
+}
+
+impl RecordLayer {
+    /// Create a new `RecordLayer` appending to the cassette at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(RecordLayer {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+}
+
+impl Layer for RecordLayer {
+    fn layer(&self, inner: Box<dyn Connector>) -> Box<dyn Connector> {
+        Box::new(Recorder {
+            inner,
+            file: self.file.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Recorder {
+    inner: Box<dyn Connector>,
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    fn record<F>(
+        &self,
+        api_url: &ApiUrl,
+        req: HttpRequest,
+        send: F,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>
+    where
+        F: FnOnce(HttpRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>,
+    {
+        let mut interaction = Interaction {
+            method: req.name().to_string(),
+            url: req.url.url(api_url, &BotToken::new(REDACTED_TOKEN)),
+            body: req.body.to_string(),
+            response: None,
+        };
+        let file = self.file.clone();
+        let response = send(req);
+
+        async move {
+            let response = response.await?;
+            interaction.response = response
+                .body
+                .as_ref()
+                .map(|body| String::from_utf8_lossy(body).into_owned());
+
+            let mut line = serde_json::to_string(&interaction).map_err(ErrorKind::from)?;
+            line.push('\n');
+            file.lock()
+                .unwrap()
+                .write_all(line.as_bytes())
+                .map_err(ErrorKind::from)?;
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+impl Connector for Recorder {
+    fn request(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.record(api_url, req, |req| self.inner.request(api_url, token, req))
+    }
+
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.record(api_url, req, |req| {
+            self.inner.upload(api_url, token, req, progress)
+        })
+    }
+
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        // File contents are binary and don't belong into the cassette.
+        self.inner.download(api_url, token, req)
+    }
+}
+
+/// Connector which answers requests with responses from a cassette.
+///
+/// Requests are matched to interactions by method name and body, every interaction
+/// is replayed only once. In non-strict mode a request without an exact match is
+/// answered with any interaction of the same method, in strict mode it fails.
+#[derive(Debug)]
+pub struct ReplayConnector {
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+    strict: bool,
+}
+
+impl ReplayConnector {
+    /// Create a new `ReplayConnector` replaying `interactions`.
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        ReplayConnector {
+            interactions: Mutex::new(interactions.into_iter().map(|i| (i, false)).collect()),
+            strict: false,
+        }
+    }
+
+    /// Create a new `ReplayConnector` replaying the cassette at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut interactions = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            interactions.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self::new(interactions))
+    }
+
+    /// Fail on requests without an exactly matching interaction.
+    ///
+    /// Defaults to `false`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn replay(&self, req: &HttpRequest) -> Option<Interaction> {
+        let mut interactions = self.interactions.lock().unwrap();
+        let exact = interactions
+            .iter_mut()
+            .find(|(interaction, used)| !*used && interaction.matches(req));
+        if let Some((interaction, used)) = exact {
+            *used = true;
+            return Some(interaction.clone());
+        }
+        if self.strict {
+            return None;
+        }
+        interactions
+            .iter()
+            .find(|(interaction, _)| interaction.matches_method(req))
+            .map(|(interaction, _)| interaction.clone())
+    }
+}
+
+impl Connector for ReplayConnector {
+    fn request(
+        &self,
+        _api_url: &ApiUrl,
+        _token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let result = match self.replay(&req) {
+            Some(interaction) => {
+                tracing::trace!(name = %req.name(), "replaying interaction");
+                Ok(HttpResponse {
+                    body: interaction.response.map(String::into_bytes),
+                })
+            }
+            None => {
+                tracing::error!(name = %req.name(), body = %req.body, "unmatched request");
+                Err(ErrorKind::UnmatchedRequest(req.name()).into())
+            }
+        };
+        future::ready(result).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use telegram_bot_raw::{ApiUrl, BotToken, ChatId, GetMe, Request, SendMessage};
+
+    use super::{RecordLayer, ReplayConnector};
+    use crate::connector::{Connector, Layer};
+    use crate::test_support::interaction;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = env::temp_dir().join(format!("telegram-bot-cassette-{}.jsonl", process::id()));
+        let _ = std::fs::remove_file(&path);
+        let secret = BotToken::new("secret");
+
+        let response = r#"{"ok":true,"result":true}"#;
+        let body = GetMe.serialize().unwrap().body.to_string();
+        let recorded = ReplayConnector::new(vec![interaction("getMe", &body, response)]);
+        let recorder = RecordLayer::new(&path).unwrap().layer(Box::new(recorded));
+        recorder
+            .request(&ApiUrl::default(), &secret, GetMe.serialize().unwrap())
+            .await
+            .unwrap();
+
+        let cassette = std::fs::read_to_string(&path).unwrap();
+        assert!(!cassette.contains("secret"));
+
+        let replay = ReplayConnector::from_file(&path).unwrap().strict(true);
+        let replayed = replay
+            .request(&ApiUrl::default(), &secret, GetMe.serialize().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(replayed.body, Some(response.as_bytes().to_vec()));
+
+        let unmatched = SendMessage::new(ChatId::new(1), "text")
+            .serialize()
+            .unwrap();
+        assert!(replay
+            .request(&ApiUrl::default(), &secret, unmatched)
+            .await
+            .is_err());
+        assert!(replay
+            .request(&ApiUrl::default(), &secret, GetMe.serialize().unwrap())
+            .await
+            .is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,51 +1,61 @@
-use std::io::{Cursor, Read};
-use std::path::Path;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use bytes::Bytes;
-use futures::{Future, FutureExt};
+use futures::{Future, FutureExt, StreamExt, TryStreamExt};
 use hyper::{
     body::to_bytes,
     client::{connect::Connect, Client},
-    header::CONTENT_TYPE,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
     http::Error as HttpError,
-    Method, Request, Uri,
+    Body, Method, Request, Response, Uri,
 };
 #[cfg(feature = "rustls")]
 use hyper_rustls::HttpsConnector;
 #[cfg(feature = "openssl")]
 use hyper_tls::HttpsConnector;
-use multipart::client::lazy::Multipart;
 use telegram_bot_raw::{
-    Body as TelegramBody, HttpRequest, HttpResponse, Method as TelegramMethod, MultipartValue, Text,
+    ApiUrl, Body as TelegramBody, BotToken, HttpRequest, HttpResponse, Method as TelegramMethod,
 };
 
-use super::Connector;
+use super::multipart::MultipartBody;
+use super::proxy::{Proxy, ProxyConnector};
+use super::{ByteStream, Connector, UploadProgress};
 use crate::errors::{Error, ErrorKind};
+use crate::runtime::{default_runtime, Runtime};
 
 #[derive(Debug)]
-pub struct HyperConnector<C>(Client<C>);
-
-enum MultipartTemporaryValue {
-    Text(Text),
-    Data { file_name: Text, data: Bytes },
+pub struct HyperConnector<C> {
+    client: Client<C>,
+    runtime: Arc<dyn Runtime>,
 }
 
 impl<C> HyperConnector<C> {
     pub fn new(client: Client<C>) -> Self {
-        HyperConnector(client)
+        HyperConnector {
+            client,
+            runtime: default_runtime(),
+        }
+    }
+
+    /// Read files of multipart uploads with `runtime` instead of the default one.
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
     }
 }
 
-impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for HyperConnector<C> {
-    fn request(
+impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> HyperConnector<C> {
+    fn send(
         &self,
-        token: &str,
+        api_url: &ApiUrl,
+        token: &BotToken,
         req: HttpRequest,
-    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
-        let uri = Uri::from_str(&req.url.url(token));
-        let client = self.0.clone();
+        progress: Option<UploadProgress>,
+    ) -> Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send>> {
+        let uri = Uri::from_str(&req.url.url(api_url, token));
+        let client = self.client.clone();
+        let runtime = self.runtime.clone();
 
         let future = async move {
             let uri = uri.map_err(HttpError::from).map_err(ErrorKind::from)?;
@@ -57,8 +67,6 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
 
             let mut http_request = Request::builder().method(method).uri(uri);
 
-            println!("edwin #145 <{:?}>, <{}>", req.url, req.body.to_string().clone());
-
             let request = match req.body {
                 TelegramBody::Empty => http_request.body(Into::<hyper::Body>::into(vec![])),
                 TelegramBody::Json(body) => {
@@ -66,84 +74,51 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
                         .parse()
                         .map_err(HttpError::from)
                         .map_err(ErrorKind::from)?;
-                    http_request
-                        .headers_mut()
-                        .map(move |headers| headers.insert(CONTENT_TYPE, content_type));
+                    if let Some(headers) = http_request.headers_mut() {
+                        headers.insert(CONTENT_TYPE, content_type);
+                    }
                     http_request.body(Into::<hyper::Body>::into(body))
                 }
                 TelegramBody::Multipart(parts) => {
-                    let mut fields = Vec::new();
-                    for (key, value) in parts {
-                        match value {
-                            MultipartValue::Text(text) => {
-                                fields.push((key, MultipartTemporaryValue::Text(text)))
-                            }
-                            MultipartValue::Path { file_name, path } => {
-                                let file_name = file_name
-                                    .or_else(|| {
-                                        AsRef::<Path>::as_ref(&path)
-                                            .file_name()
-                                            .and_then(|s| s.to_str())
-                                            .map(Into::into)
-                                    })
-                                    .ok_or(ErrorKind::InvalidMultipartFilename)?;
-
-                                let data = tokio::fs::read(path).await.map_err(ErrorKind::from)?;
-                                fields.push((
-                                    key,
-                                    MultipartTemporaryValue::Data {
-                                        file_name,
-                                        data: data.into(),
-                                    },
-                                ))
-                            }
-                            MultipartValue::Data { file_name, data } => fields
-                                .push((key, MultipartTemporaryValue::Data { file_name, data })),
-                        }
-                    }
-
-                    let mut prepared = {
-                        let mut part = Multipart::new();
-                        for (key, value) in &fields {
-                            match value {
-                                MultipartTemporaryValue::Text(text) => {
-                                    part.add_text(*key, text.as_str());
-                                }
-                                MultipartTemporaryValue::Data { file_name, data } => {
-                                    part.add_stream(
-                                        *key,
-                                        Cursor::new(data),
-                                        Some(file_name.as_str()),
-                                        None,
-                                    );
-                                }
-                            }
-                        }
-                        part.prepare().map_err(|err| err.error)
-                    }
+                    let body = MultipartBody::new(parts, runtime).await?;
+
+                    let content_type = format!(
+                        "multipart/form-data;boundary={bound}",
+                        bound = body.boundary
+                    )
+                    .parse()
+                    .map_err(HttpError::from)
                     .map_err(ErrorKind::from)?;
-
-                    let boundary = prepared.boundary();
-
-                    let content_type =
-                        format!("multipart/form-data;boundary={bound}", bound = boundary)
-                            .parse()
-                            .map_err(HttpError::from)
-                            .map_err(ErrorKind::from)?;
-                    http_request.headers_mut().map(move |headers| {
+                    let content_length = body.length.into();
+                    if let Some(headers) = http_request.headers_mut() {
                         headers.insert(CONTENT_TYPE, content_type);
-                    });
+                        headers.insert(CONTENT_LENGTH, content_length);
+                    }
 
-                    let mut bytes = Vec::new();
-                    prepared.read_to_end(&mut bytes).map_err(ErrorKind::from)?;
-                    http_request.body(bytes.into())
+                    http_request.body(Body::wrap_stream(body.into_stream(progress)))
                 }
                 body => panic!("Unknown body type {:?}", body),
             }
             .map_err(ErrorKind::from)?;
 
             let response = client.request(request).await.map_err(ErrorKind::from)?;
-            let whole_chunk = to_bytes(response.into_body()).await;
+            Ok::<Response<Body>, Error>(response)
+        };
+
+        future.boxed()
+    }
+
+    fn send_buffered(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: Option<UploadProgress>,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let response = self.send(api_url, token, req, progress);
+
+        let future = async move {
+            let whole_chunk = to_bytes(response.await?.into_body()).await;
 
             let body = whole_chunk
                 .iter()
@@ -152,10 +127,6 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
                     acc
                 });
 
-            if body.len() > 0 {
-                println!("edwin #156 <{}>, <{}>", String::from_utf8_lossy(&body), body.len());
-            }
-
             Ok::<HttpResponse, Error>(HttpResponse { body: Some(body) })
         };
 
@@ -163,12 +134,74 @@ impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for
     }
 }
 
+impl<C: Connect + std::fmt::Debug + 'static + Clone + Send + Sync> Connector for HyperConnector<C> {
+    fn request(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.send_buffered(api_url, token, req, None)
+    }
+
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.send_buffered(api_url, token, req, Some(progress))
+    }
+
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        let response = self.send(api_url, token, req, None);
+
+        let future = async move {
+            let response = response.await?;
+            if !response.status().is_success() {
+                return Err(ErrorKind::HttpStatus(response.status().as_u16()).into());
+            }
+            let body = response
+                .into_body()
+                .map_err(|err| Error::from(ErrorKind::from(err)));
+            Ok::<ByteStream, Error>(body.boxed())
+        };
+
+        future.boxed()
+    }
+}
+
+/// Create the default connector, tunneling connections through the proxy
+/// configured in the environment, see [`Proxy::from_env`].
+///
+/// [`Proxy::from_env`]: ../struct.Proxy.html#method.from_env
 pub fn default_connector() -> Result<Box<dyn Connector>, Error> {
+    https_connector(Proxy::from_env())
+}
+
+/// Create a connector tunneling connections through `proxy`.
+pub fn proxy_connector(proxy: Proxy) -> Result<Box<dyn Connector>, Error> {
+    https_connector(Some(proxy))
+}
+
+fn https_connector(proxy: Option<Proxy>) -> Result<Box<dyn Connector>, Error> {
+    let connector = ProxyConnector::new(proxy);
+
     #[cfg(feature = "rustls")]
-    let connector = HttpsConnector::new();
+    let connector = {
+        let mut config = rustls_crate::ClientConfig::new();
+        config.root_store = rustls_native_certs::load_native_certs().map_err(ErrorKind::from)?;
+        HttpsConnector::from((connector, config))
+    };
 
     #[cfg(feature = "openssl")]
-    let connector = HttpsConnector::new();
+    let connector = HttpsConnector::new_with_connector(connector);
 
     Ok(Box::new(HyperConnector::new(
         Client::builder().build(connector),
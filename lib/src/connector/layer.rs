@@ -0,0 +1,379 @@
+//! Middleware layers wrapping a connector.
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{Future, FutureExt};
+use telegram_bot_raw::{ApiUrl, BotToken, HttpRequest, HttpResponse};
+
+use super::{ByteStream, Connector, UploadProgress};
+use crate::errors::Error;
+
+/// Decorates a connector with additional behaviour, e.g. logging or fault injection.
+///
+/// Layers are stacked with [`ApiBuilder::layer`], any
+/// `Fn(Box<dyn Connector>) -> Box<dyn Connector>` closure is a layer as well.
+///
+/// [`ApiBuilder::layer`]: ../../struct.ApiBuilder.html#method.layer
+pub trait Layer: Send + Sync {
+    /// Wrap `inner` connector.
+    fn layer(&self, inner: Box<dyn Connector>) -> Box<dyn Connector>;
+}
+
+impl<F> Layer for F
+where
+    F: Fn(Box<dyn Connector>) -> Box<dyn Connector> + Send + Sync,
+{
+    fn layer(&self, inner: Box<dyn Connector>) -> Box<dyn Connector> {
+        self(inner)
+    }
+}
+
+type TimingCallback =
+    Arc<dyn Fn(&'static str, Duration, Result<&HttpResponse, &Error>) + Send + Sync>;
+
+/// Layer which measures the time spent on every request.
+///
+/// Timings are reported as `tracing` events and passed to the optional callback.
+#[derive(Clone, Default)]
+pub struct TimingLayer {
+    callback: Option<TimingCallback>,
+}
+
+impl TimingLayer {
+    /// Create a new `TimingLayer` which only emits `tracing` events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` with method name, elapsed time and result of every request.
+    pub fn callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&'static str, Duration, Result<&HttpResponse, &Error>) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl Layer for TimingLayer {
+    fn layer(&self, inner: Box<dyn Connector>) -> Box<dyn Connector> {
+        Box::new(Timing {
+            inner,
+            callback: self.callback.clone(),
+        })
+    }
+}
+
+struct Timing {
+    inner: Box<dyn Connector>,
+    callback: Option<TimingCallback>,
+}
+
+impl fmt::Debug for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timing")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl Timing {
+    fn measure(
+        &self,
+        name: &'static str,
+        response: Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let callback = self.callback.clone();
+        let started = Instant::now();
+
+        async move {
+            let result = response.await;
+            let elapsed = started.elapsed();
+            tracing::debug!(name = %name, elapsed = ?elapsed, ok = result.is_ok(), "request finished");
+            if let Some(callback) = callback {
+                callback(name, elapsed, result.as_ref());
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+impl Connector for Timing {
+    fn request(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let name = req.name();
+        self.measure(name, self.inner.request(api_url, token, req))
+    }
+
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let name = req.name();
+        self.measure(name, self.inner.upload(api_url, token, req, progress))
+    }
+
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        let name = req.name();
+        let callback = self.callback.clone();
+        let started = Instant::now();
+        let response = self.inner.download(api_url, token, req);
+
+        async move {
+            let result = response.await;
+            let elapsed = started.elapsed();
+            tracing::debug!(name = %name, elapsed = ?elapsed, ok = result.is_ok(), "download started");
+            match callback {
+                Some(callback) => report_download(result, |result| callback(name, elapsed, result)),
+                None => result,
+            }
+        }
+        .boxed()
+    }
+}
+
+type RequestCallback = Arc<dyn Fn(&HttpRequest) + Send + Sync>;
+type ResponseCallback = Arc<dyn Fn(&'static str, Result<&HttpResponse, &Error>) + Send + Sync>;
+
+/// Layer which passes every request and response to user callbacks.
+///
+/// # Examples
+///
+/// ```rust
+/// use telegram_bot::Api;
+/// use telegram_bot::connector::InspectLayer;
+///
+/// let inspect = InspectLayer::new()
+///     .request(|request| println!("-> {} {}", request.name(), request.body))
+///     .response(|name, response| println!("<- {} {:?}", name, response));
+///
+/// let api = Api::builder("token").layer(inspect).build();
+/// ```
+#[derive(Clone, Default)]
+pub struct InspectLayer {
+    on_request: Option<RequestCallback>,
+    on_response: Option<ResponseCallback>,
+}
+
+impl InspectLayer {
+    /// Create a new `InspectLayer` without callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` before every request is sent.
+    pub fn request<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&HttpRequest) + Send + Sync + 'static,
+    {
+        self.on_request = Some(Arc::new(callback));
+        self
+    }
+
+    /// Call `callback` with method name and result of every request.
+    pub fn response<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&'static str, Result<&HttpResponse, &Error>) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl Layer for InspectLayer {
+    fn layer(&self, inner: Box<dyn Connector>) -> Box<dyn Connector> {
+        Box::new(Inspect {
+            inner,
+            on_request: self.on_request.clone(),
+            on_response: self.on_response.clone(),
+        })
+    }
+}
+
+struct Inspect {
+    inner: Box<dyn Connector>,
+    on_request: Option<RequestCallback>,
+    on_response: Option<ResponseCallback>,
+}
+
+impl fmt::Debug for Inspect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspect")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl Inspect {
+    fn inspect<F>(
+        &self,
+        req: HttpRequest,
+        send: F,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>
+    where
+        F: FnOnce(HttpRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>,
+    {
+        if let Some(ref on_request) = self.on_request {
+            on_request(&req);
+        }
+
+        let name = req.name();
+        let on_response = self.on_response.clone();
+        let response = send(req);
+
+        async move {
+            let result = response.await;
+            if let Some(on_response) = on_response {
+                on_response(name, result.as_ref());
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+impl Connector for Inspect {
+    fn request(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.inspect(req, |req| self.inner.request(api_url, token, req))
+    }
+
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.inspect(req, |req| self.inner.upload(api_url, token, req, progress))
+    }
+
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        if let Some(ref on_request) = self.on_request {
+            on_request(&req);
+        }
+
+        let name = req.name();
+        let on_response = self.on_response.clone();
+        let response = self.inner.download(api_url, token, req);
+
+        async move {
+            let result = response.await;
+            match on_response {
+                Some(on_response) => report_download(result, |result| on_response(name, result)),
+                None => result,
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Pass the outcome of a download to a callback expecting a buffered response,
+/// the body of a successful download is not available to the callback.
+fn report_download<F>(result: Result<ByteStream, Error>, callback: F) -> Result<ByteStream, Error>
+where
+    F: FnOnce(Result<&HttpResponse, &Error>),
+{
+    match &result {
+        Ok(_) => callback(Ok(&HttpResponse { body: None })),
+        Err(err) => callback(Err(err)),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use telegram_bot_raw::{GetMe, Request, RequestUrl};
+
+    use super::*;
+    use crate::test_support::replay_connector;
+
+    type Calls = Arc<Mutex<Vec<(&'static str, bool)>>>;
+
+    fn replay() -> Box<dyn Connector> {
+        let ok = r#"{"ok":true,"result":true}"#;
+        Box::new(replay_connector(&[("getMe", ok), ("downloadFile", ok)]))
+    }
+
+    async fn send_all(connector: Box<dyn Connector>) {
+        let (url, token) = (ApiUrl::default(), BotToken::new("token"));
+        let get_me = GetMe.serialize().unwrap();
+        let mut download = get_me.clone();
+        download.url = RequestUrl::file("documents/file_1.txt");
+        let mut unknown = get_me.clone();
+        unknown.url = RequestUrl::method("getChat");
+
+        assert!(connector.request(&url, &token, get_me).await.is_ok());
+        assert!(connector.download(&url, &token, download).await.is_ok());
+        assert!(connector.request(&url, &token, unknown).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timing_layer() {
+        let calls: Calls = Default::default();
+        let recorded = calls.clone();
+        let timing = TimingLayer::new().callback(move |name, _, result| {
+            recorded.lock().unwrap().push((name, result.is_ok()));
+        });
+
+        send_all(timing.layer(replay())).await;
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [("getMe", true), ("downloadFile", true), ("getChat", false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inspect_layer() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded_requests = requests.clone();
+        let calls: Calls = Default::default();
+        let recorded = calls.clone();
+        let inspect = InspectLayer::new()
+            .request(move |request| recorded_requests.lock().unwrap().push(request.name()))
+            .response(move |name, result| {
+                if name == "getMe" {
+                    let body = result.unwrap().body.as_ref().unwrap();
+                    assert_eq!(body, br#"{"ok":true,"result":true}"#);
+                }
+                recorded.lock().unwrap().push((name, result.is_ok()));
+            });
+
+        send_all(inspect.layer(replay())).await;
+        assert_eq!(
+            *requests.lock().unwrap(),
+            ["getMe", "downloadFile", "getChat"]
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [("getMe", true), ("downloadFile", true), ("getChat", false)]
+        );
+    }
+}
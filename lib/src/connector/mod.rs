@@ -1,21 +1,68 @@
 //! Connector with hyper backend.
 
+pub mod cassette;
 pub mod hyper;
+pub mod layer;
+pub(crate) mod multipart;
+pub mod proxy;
+
+pub use self::layer::{InspectLayer, Layer, TimingLayer};
+pub use self::proxy::Proxy;
 
 use std::fmt::Debug;
 use std::pin::Pin;
+use std::sync::Arc;
 
-use futures::Future;
-use telegram_bot_raw::{HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::{stream, Future, FutureExt, Stream, StreamExt, TryFutureExt};
+use telegram_bot_raw::{ApiUrl, BotToken, HttpRequest, HttpResponse};
 
 use crate::errors::Error;
 
+/// Callback receiving the number of bytes sent so far and the total size of the request body.
+pub type UploadProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// Stream of response body chunks.
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>;
+
 pub trait Connector: Debug + Send + Sync {
     fn request(
         &self,
-        token: &str,
+        api_url: &ApiUrl,
+        token: &BotToken,
         req: HttpRequest,
     ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>;
+
+    /// Send the request, reporting the upload progress of its body to `progress`.
+    ///
+    /// Connectors which don't stream request bodies ignore `progress`.
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        let _ = progress;
+        self.request(api_url, token, req)
+    }
+
+    /// Send the request and stream the response body, used to download files.
+    ///
+    /// Connectors which don't stream response bodies return the whole body as a single chunk.
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        self.request(api_url, token, req)
+            .map_ok(|response| {
+                let body = Bytes::from(response.body.unwrap_or_default());
+                stream::once(async { Ok(body) }).boxed()
+            })
+            .boxed()
+    }
 }
 
 pub fn default_connector() -> Box<dyn Connector> {
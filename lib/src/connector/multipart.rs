@@ -0,0 +1,194 @@
+//! Streaming `multipart/form-data` request bodies.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+
+use telegram_bot_raw::{Multipart, MultipartValue, Text};
+
+use super::UploadProgress;
+use crate::errors::{Error, ErrorKind};
+use crate::runtime::Runtime;
+
+enum Chunk {
+    Bytes(Bytes),
+    File(PathBuf),
+}
+
+/// Multipart body which is read from disk while it is being sent.
+pub(crate) struct MultipartBody {
+    pub boundary: String,
+    pub length: u64,
+    chunks: Vec<Chunk>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl MultipartBody {
+    /// Prepare the body, files are read by `runtime` once the body is sent.
+    pub(crate) async fn new(parts: Multipart, runtime: Arc<dyn Runtime>) -> Result<Self, Error> {
+        let boundary = boundary();
+        let mut chunks = Vec::new();
+        let mut length = 0;
+
+        for (key, value) in parts {
+            let (file_name, content, content_length) = match value {
+                MultipartValue::Text(text) => {
+                    let text = text_bytes(text);
+                    let len = text.len() as u64;
+                    (None, Chunk::Bytes(text), len)
+                }
+                MultipartValue::Path { file_name, path } => {
+                    let file_name = file_name
+                        .or_else(|| {
+                            AsRef::<Path>::as_ref(&path)
+                                .file_name()
+                                .and_then(|s| s.to_str())
+                                .map(Into::into)
+                        })
+                        .ok_or(ErrorKind::InvalidMultipartFilename)?;
+                    let path = PathBuf::from(path.as_str());
+                    let len = runtime
+                        .file_len(path.clone())
+                        .await
+                        .map_err(ErrorKind::from)?;
+                    (Some(file_name), Chunk::File(path), len)
+                }
+                MultipartValue::Data { file_name, data } => {
+                    let len = data.len() as u64;
+                    (Some(file_name), Chunk::Bytes(data), len)
+                }
+            };
+
+            let mut header = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                boundary,
+                escape(key)
+            );
+            if let Some(ref file_name) = file_name {
+                header.push_str(&format!(
+                    "; filename=\"{}\"\r\nContent-Type: application/octet-stream",
+                    escape(file_name.as_str())
+                ));
+            }
+            header.push_str("\r\n\r\n");
+
+            length += header.len() as u64 + content_length + 2;
+            chunks.push(Chunk::Bytes(header.into()));
+            chunks.push(content);
+            chunks.push(Chunk::Bytes(Bytes::from_static(b"\r\n")));
+        }
+
+        let footer = format!("--{}--\r\n", boundary);
+        length += footer.len() as u64;
+        chunks.push(Chunk::Bytes(footer.into()));
+
+        Ok(MultipartBody {
+            boundary,
+            length,
+            chunks,
+            runtime,
+        })
+    }
+
+    /// Returns the stream of body chunks, calling `progress` after every chunk.
+    pub(crate) fn into_stream(
+        self,
+        progress: Option<UploadProgress>,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+        let total = self.length;
+        let runtime = self.runtime;
+        let mut sent = 0;
+        stream::iter(self.chunks)
+            .map(move |chunk| match chunk {
+                Chunk::Bytes(bytes) => stream::once(async { Ok(bytes) }).boxed(),
+                Chunk::File(path) => runtime.read_file(path),
+            })
+            .flatten()
+            .inspect(move |chunk| {
+                if let (Some(progress), Ok(chunk)) = (&progress, chunk) {
+                    sent += chunk.len() as u64;
+                    progress(sent, total);
+                }
+            })
+    }
+}
+
+fn text_bytes(text: Text) -> Bytes {
+    Bytes::copy_from_slice(text.as_str().as_bytes())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn boundary() -> String {
+    let state = RandomState::new();
+    let random = |salt| {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(salt);
+        hasher.finish()
+    };
+    format!("telegram-bot-{:016x}{:016x}", random(0), random(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::runtime::default_runtime;
+
+    #[tokio::test]
+    async fn test_multipart_body() {
+        let path = env::temp_dir().join(format!("telegram-bot-upload-{}.txt", process::id()));
+        std::fs::write(&path, vec![b'x'; 64 * 1024 + 1]).unwrap();
+
+        let parts = vec![
+            ("chat_id", MultipartValue::Text("42".into())),
+            (
+                "document",
+                MultipartValue::Path {
+                    path: path.to_str().unwrap().into(),
+                    file_name: Some("doc\".txt".into()),
+                },
+            ),
+        ];
+        let body = MultipartBody::new(parts, default_runtime()).await.unwrap();
+        let boundary = body.boundary.clone();
+        let length = body.length;
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let progress = reported.clone();
+        let progress: UploadProgress =
+            Arc::new(move |sent, total| progress.lock().unwrap().push((sent, total)));
+        let chunks = body.into_stream(Some(progress)).collect::<Vec<_>>().await;
+        let bytes = chunks
+            .into_iter()
+            .map(Result::unwrap)
+            .fold(Vec::new(), |mut acc, chunk| {
+                acc.extend_from_slice(&chunk);
+                acc
+            });
+
+        assert_eq!(bytes.len() as u64, length);
+        let body = String::from_utf8(bytes).unwrap();
+        assert!(body.starts_with(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n42\r\n",
+            boundary
+        )));
+        assert!(body.contains("name=\"document\"; filename=\"doc%22.txt\"\r\n"));
+        assert!(body.ends_with(&format!("\r\n--{}--\r\n", boundary)));
+        assert_eq!(reported.lock().unwrap().last(), Some(&(length, length)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
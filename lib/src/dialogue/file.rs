@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::{DialogueKey, DialogueStorage, StoredDialogue};
+use crate::atomic_file;
+use crate::errors::{Error, ErrorKind};
+
+/// `DialogueStorage` which keeps dialogues in a JSON file.
+///
+/// Writes replace the whole file the same way as [`FileOffsetStore`] does.
+/// Every operation reads the whole file, prefer [`SqliteDialogueStorage`]
+/// for many concurrent dialogues.
+///
+/// [`FileOffsetStore`]: ../struct.FileOffsetStore.html
+/// [`SqliteDialogueStorage`]: struct.SqliteDialogueStorage.html
+#[derive(Debug)]
+pub struct FileDialogueStorage {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileDialogueStorage {
+    /// Create a new `FileDialogueStorage` keeping dialogues in the file at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileDialogueStorage {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<HashMap<String, StoredDialogue>, Error> {
+        match fs::read(&self.path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .map_err(|err| ErrorKind::Storage(Box::new(err)).into()),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(err) => Err(ErrorKind::from(err).into()),
+        }
+    }
+
+    fn write(&self, dialogues: &HashMap<String, StoredDialogue>) -> Result<(), Error> {
+        let contents = serde_json::to_vec(dialogues).map_err(ErrorKind::from)?;
+        atomic_file::write(&self.path, &contents).map_err(ErrorKind::from)?;
+        Ok(())
+    }
+}
+
+impl DialogueStorage for FileDialogueStorage {
+    fn load(&self, key: DialogueKey) -> Result<Option<StoredDialogue>, Error> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self.read()?.remove(&key.to_string()))
+    }
+
+    fn store(&self, key: DialogueKey, dialogue: StoredDialogue) -> Result<(), Error> {
+        let _lock = self.lock.lock().unwrap();
+        let mut dialogues = self.read()?;
+        dialogues.insert(key.to_string(), dialogue);
+        self.write(&dialogues)
+    }
+
+    fn remove(&self, key: DialogueKey) -> Result<(), Error> {
+        let _lock = self.lock.lock().unwrap();
+        let mut dialogues = self.read()?;
+        if dialogues.remove(&key.to_string()).is_some() {
+            self.write(&dialogues)?;
+        }
+        Ok(())
+    }
+
+    fn remove_updated_before(&self, updated_at: u64) -> Result<usize, Error> {
+        let _lock = self.lock.lock().unwrap();
+        let mut dialogues = self.read()?;
+        let count = dialogues.len();
+        dialogues.retain(|_, dialogue| dialogue.updated_at >= updated_at);
+        let removed = count - dialogues.len();
+        if removed > 0 {
+            self.write(&dialogues)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn test_file_dialogue_storage() {
+        let path = env::temp_dir().join(format!("telegram-bot-dialogues-{}", process::id()));
+        let key = DialogueKey {
+            chat: 1.into(),
+            user: 2.into(),
+        };
+        let dialogue = StoredDialogue {
+            state: "\"state\"".into(),
+            updated_at: 42,
+        };
+        let storage = FileDialogueStorage::new(&path);
+        assert_eq!(storage.load(key).unwrap(), None);
+
+        storage.store(key, dialogue.clone()).unwrap();
+        assert_eq!(
+            FileDialogueStorage::new(&path).load(key).unwrap(),
+            Some(dialogue.clone())
+        );
+        storage.remove(key).unwrap();
+        assert_eq!(storage.load(key).unwrap(), None);
+
+        storage.store(key, dialogue).unwrap();
+        assert_eq!(storage.remove_updated_before(42).unwrap(), 0);
+        assert_eq!(storage.remove_updated_before(43).unwrap(), 1);
+        assert_eq!(storage.load(key).unwrap(), None);
+
+        fs::write(&path, "garbage").unwrap();
+        assert!(storage.load(key).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+//! Per-chat conversation state for multi-step flows.
+//!
+//! [`Dialogues`] keeps a typed state for every chat and user in a [`DialogueStorage`],
+//! serialized with serde. [`MemoryDialogueStorage`] keeps states in memory,
+//! [`FileDialogueStorage`] in a file and, with the `sqlite` feature,
+//! [`SqliteDialogueStorage`] in a SQLite database.
+//!
+//! ```rust
+//! # use std::sync::Arc;
+//! # use serde::{Deserialize, Serialize};
+//! # use telegram_bot::{Api, Message, prelude::*};
+//! # use telegram_bot::dialogue::{DialogueState, Dialogues, MemoryDialogueStorage};
+//! #
+//! #[derive(Serialize, Deserialize)]
+//! enum Signup {
+//!     AskAge { name: String },
+//! }
+//!
+//! # async fn handle(api: Api, message: Message) -> Result<(), telegram_bot::Error> {
+//! # let dialogues: Dialogues<Signup> = Dialogues::new(Arc::new(MemoryDialogueStorage::new()));
+//! let text = match message.kind {
+//!     telegram_bot::MessageKind::Text { ref data, .. } => data.clone(),
+//!     _ => return Ok(()),
+//! };
+//! match dialogues.get(&message)? {
+//!     DialogueState::Inactive | DialogueState::Expired => {
+//!         dialogues.update(&message, &Signup::AskAge { name: text })?;
+//!         api.send(message.text_reply("How old are you?")).await?;
+//!     }
+//!     DialogueState::Active(Signup::AskAge { name }) => {
+//!         dialogues.exit(&message)?;
+//!         api.send(message.text_reply(format!("{} is {} years old", name, text))).await?;
+//!     }
+//!     DialogueState::Cancelled => {
+//!         api.send(message.text_reply("Cancelled")).await?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Dialogues`]: struct.Dialogues.html
+//! [`DialogueStorage`]: trait.DialogueStorage.html
+//! [`MemoryDialogueStorage`]: struct.MemoryDialogueStorage.html
+//! [`FileDialogueStorage`]: struct.FileDialogueStorage.html
+//! [`SqliteDialogueStorage`]: struct.SqliteDialogueStorage.html
+
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use self::file::FileDialogueStorage;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteDialogueStorage;
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use telegram_bot_raw::{ChatId, Message, UserId};
+
+use crate::errors::{Error, ErrorKind};
+use crate::filter::command;
+
+/// Identifies the dialogue of a user in a chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DialogueKey {
+    pub chat: ChatId,
+    pub user: UserId,
+}
+
+impl DialogueKey {
+    /// Returns the key of the dialogue `message` belongs to.
+    pub fn from_message(message: &Message) -> Self {
+        DialogueKey {
+            chat: message.chat.id(),
+            user: message.from.id,
+        }
+    }
+}
+
+impl fmt::Display for DialogueKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.chat, self.user)
+    }
+}
+
+/// Serialized state of a dialogue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredDialogue {
+    /// State serialized as JSON.
+    pub state: String,
+    /// Unix time of the last update of the state in milliseconds.
+    pub updated_at: u64,
+}
+
+/// Storage for the states of dialogues.
+///
+/// Implement this trait to keep dialogues next to the state of your bot.
+pub trait DialogueStorage: Debug + Send + Sync {
+    /// Returns the stored dialogue, if any.
+    fn load(&self, key: DialogueKey) -> Result<Option<StoredDialogue>, Error>;
+
+    /// Replace the stored dialogue.
+    fn store(&self, key: DialogueKey, dialogue: StoredDialogue) -> Result<(), Error>;
+
+    /// Remove the stored dialogue.
+    fn remove(&self, key: DialogueKey) -> Result<(), Error>;
+
+    /// Remove all dialogues last updated before `updated_at`, returns how many were removed.
+    fn remove_updated_before(&self, updated_at: u64) -> Result<usize, Error>;
+}
+
+/// `DialogueStorage` which keeps dialogues in memory.
+#[derive(Debug, Default)]
+pub struct MemoryDialogueStorage {
+    dialogues: Mutex<HashMap<DialogueKey, StoredDialogue>>,
+}
+
+impl MemoryDialogueStorage {
+    /// Create a new empty `MemoryDialogueStorage`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DialogueStorage for MemoryDialogueStorage {
+    fn load(&self, key: DialogueKey) -> Result<Option<StoredDialogue>, Error> {
+        Ok(self.dialogues.lock().unwrap().get(&key).cloned())
+    }
+
+    fn store(&self, key: DialogueKey, dialogue: StoredDialogue) -> Result<(), Error> {
+        self.dialogues.lock().unwrap().insert(key, dialogue);
+        Ok(())
+    }
+
+    fn remove(&self, key: DialogueKey) -> Result<(), Error> {
+        self.dialogues.lock().unwrap().remove(&key);
+        Ok(())
+    }
+
+    fn remove_updated_before(&self, updated_at: u64) -> Result<usize, Error> {
+        let mut dialogues = self.dialogues.lock().unwrap();
+        let count = dialogues.len();
+        dialogues.retain(|_, dialogue| dialogue.updated_at >= updated_at);
+        Ok(count - dialogues.len())
+    }
+}
+
+/// State of the dialogue a message belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogueState<S> {
+    /// There is no dialogue.
+    Inactive,
+    /// The dialogue is in state `S`.
+    Active(S),
+    /// The dialogue timed out and was removed.
+    Expired,
+    /// The message is the cancel command, the dialogue was removed.
+    Cancelled,
+}
+
+/// Typed dialogues kept in a `DialogueStorage`.
+pub struct Dialogues<S> {
+    storage: Arc<dyn DialogueStorage>,
+    timeout: Option<Duration>,
+    cancel_command: Option<String>,
+    state: PhantomData<fn() -> S>,
+}
+
+impl<S: Serialize + DeserializeOwned> Dialogues<S> {
+    /// Create a new `Dialogues` keeping states in `storage`.
+    pub fn new(storage: Arc<dyn DialogueStorage>) -> Self {
+        Dialogues {
+            storage,
+            timeout: None,
+            cancel_command: None,
+            state: PhantomData,
+        }
+    }
+
+    /// Expire dialogues which weren't updated for `timeout`.
+    ///
+    /// Expired dialogues are removed once they are looked up again, call
+    /// [`purge_expired`](#method.purge_expired) regularly to remove abandoned ones.
+    /// Dialogues never expire by default.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Cancel any dialogue with the bot command `name`, e.g. `"cancel"` for `/cancel`.
+    pub fn cancel_command(&mut self, name: &str) -> &mut Self {
+        self.cancel_command = Some(name.to_string());
+        self
+    }
+
+    /// Returns the state of the dialogue `message` belongs to.
+    pub fn get(&self, message: &Message) -> Result<DialogueState<S>, Error> {
+        let key = DialogueKey::from_message(message);
+        let dialogue = match self.storage.load(key)? {
+            Some(dialogue) => dialogue,
+            None => return Ok(DialogueState::Inactive),
+        };
+
+        if let (Some(cancel), Some(name)) = (&self.cancel_command, command(message)) {
+            if cancel == name {
+                tracing::debug!(dialogue = %key, "dialogue cancelled");
+                self.storage.remove(key)?;
+                return Ok(DialogueState::Cancelled);
+            }
+        }
+        if let Some(timeout) = self.timeout {
+            if Duration::from_millis(now().saturating_sub(dialogue.updated_at)) >= timeout {
+                tracing::debug!(dialogue = %key, "dialogue expired");
+                self.storage.remove(key)?;
+                return Ok(DialogueState::Expired);
+            }
+        }
+
+        let state = serde_json::from_str(&dialogue.state).map_err(ErrorKind::from)?;
+        Ok(DialogueState::Active(state))
+    }
+
+    /// Start or continue the dialogue `message` belongs to in `state`.
+    pub fn update(&self, message: &Message, state: &S) -> Result<(), Error> {
+        let dialogue = StoredDialogue {
+            state: serde_json::to_string(state).map_err(ErrorKind::from)?,
+            updated_at: now(),
+        };
+        self.storage
+            .store(DialogueKey::from_message(message), dialogue)
+    }
+
+    /// End the dialogue `message` belongs to.
+    pub fn exit(&self, message: &Message) -> Result<(), Error> {
+        self.storage.remove(DialogueKey::from_message(message))
+    }
+
+    /// Remove all expired dialogues, returns how many were removed.
+    pub fn purge_expired(&self) -> Result<usize, Error> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout.as_millis() as u64,
+            None => return Ok(0),
+        };
+        let removed = self
+            .storage
+            .remove_updated_before(now().saturating_sub(timeout))?;
+        if removed > 0 {
+            tracing::debug!(removed = removed, "expired dialogues purged");
+        }
+        Ok(removed)
+    }
+}
+
+impl<S> fmt::Debug for Dialogues<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dialogues")
+            .field("storage", &self.storage)
+            .field("timeout", &self.timeout)
+            .field("cancel_command", &self.cancel_command)
+            .finish()
+    }
+}
+
+/// Returns the current unix time in milliseconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str) -> Message {
+        let json = format!(
+            r#"{{"message_id":1,"date":0,"chat":{{"id":1,"type":"private","first_name":"A"}},
+                "from":{{"id":1,"is_bot":false,"first_name":"A"}},"text":"{}"}}"#,
+            text
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_dialogues() {
+        let storage = Arc::new(MemoryDialogueStorage::new());
+        let mut dialogues = Dialogues::<Vec<String>>::new(storage.clone());
+        dialogues.cancel_command("cancel");
+
+        assert_eq!(
+            dialogues.get(&message("a")).unwrap(),
+            DialogueState::Inactive
+        );
+        dialogues.update(&message("a"), &vec!["a".into()]).unwrap();
+        assert_eq!(
+            dialogues.get(&message("b")).unwrap(),
+            DialogueState::Active(vec!["a".to_string()])
+        );
+        assert_eq!(
+            dialogues.get(&message("/cancel")).unwrap(),
+            DialogueState::Cancelled
+        );
+        assert_eq!(
+            dialogues.get(&message("b")).unwrap(),
+            DialogueState::Inactive
+        );
+
+        dialogues.update(&message("a"), &Vec::new()).unwrap();
+        let key = DialogueKey::from_message(&message("a"));
+        let mut stored = storage.load(key).unwrap().unwrap();
+        stored.updated_at -= 60_000;
+        storage.store(key, stored).unwrap();
+        dialogues.timeout(Duration::from_millis(30_500));
+        assert_eq!(
+            dialogues.get(&message("b")).unwrap(),
+            DialogueState::Expired
+        );
+        assert_eq!(storage.load(key).unwrap(), None);
+
+        dialogues.update(&message("a"), &Vec::new()).unwrap();
+        assert_eq!(dialogues.purge_expired().unwrap(), 0);
+        let mut stored = storage.load(key).unwrap().unwrap();
+        stored.updated_at -= 31_000;
+        storage.store(key, stored).unwrap();
+        assert_eq!(dialogues.purge_expired().unwrap(), 1);
+        assert_eq!(storage.load(key).unwrap(), None);
+    }
+}
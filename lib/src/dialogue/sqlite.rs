@@ -0,0 +1,129 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{DialogueKey, DialogueStorage, StoredDialogue};
+use crate::errors::{Error, ErrorKind};
+
+/// `DialogueStorage` which keeps dialogues in a SQLite database.
+#[derive(Debug)]
+pub struct SqliteDialogueStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDialogueStorage {
+    /// Open the database at `path`, creating the `dialogues` table if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Keep dialogues in the `dialogues` table of `connection`, creating it if needed.
+    pub fn new(connection: Connection) -> Result<Self, Error> {
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS dialogues (
+                    chat_id INTEGER NOT NULL,
+                    user_id INTEGER NOT NULL,
+                    state TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL,
+                    PRIMARY KEY (chat_id, user_id)
+                )",
+                params![],
+            )
+            .map_err(sqlite_error)?;
+        Ok(SqliteDialogueStorage {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl DialogueStorage for SqliteDialogueStorage {
+    fn load(&self, key: DialogueKey) -> Result<Option<StoredDialogue>, Error> {
+        let connection = self.connection.lock().unwrap();
+        let dialogue = connection
+            .query_row(
+                "SELECT state, updated_at FROM dialogues WHERE chat_id = ?1 AND user_id = ?2",
+                params![i64::from(key.chat), i64::from(key.user)],
+                |row| {
+                    Ok(StoredDialogue {
+                        state: row.get(0)?,
+                        updated_at: row.get::<_, i64>(1)? as u64,
+                    })
+                },
+            )
+            .optional()
+            .map_err(sqlite_error)?;
+        Ok(dialogue)
+    }
+
+    fn store(&self, key: DialogueKey, dialogue: StoredDialogue) -> Result<(), Error> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute(
+                "INSERT OR REPLACE INTO dialogues (chat_id, user_id, state, updated_at)
+                    VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    i64::from(key.chat),
+                    i64::from(key.user),
+                    dialogue.state,
+                    dialogue.updated_at as i64
+                ],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: DialogueKey) -> Result<(), Error> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute(
+                "DELETE FROM dialogues WHERE chat_id = ?1 AND user_id = ?2",
+                params![i64::from(key.chat), i64::from(key.user)],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn remove_updated_before(&self, updated_at: u64) -> Result<usize, Error> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute(
+                "DELETE FROM dialogues WHERE updated_at < ?1",
+                params![updated_at as i64],
+            )
+            .map_err(sqlite_error)
+    }
+}
+
+fn sqlite_error(err: rusqlite::Error) -> Error {
+    ErrorKind::Storage(Box::new(err)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_dialogue_storage() {
+        let storage = SqliteDialogueStorage::new(Connection::open_in_memory().unwrap()).unwrap();
+        let key = DialogueKey {
+            chat: 1.into(),
+            user: 2.into(),
+        };
+        let dialogue = StoredDialogue {
+            state: "\"state\"".into(),
+            updated_at: 42,
+        };
+        assert_eq!(storage.load(key).unwrap(), None);
+        storage.store(key, dialogue.clone()).unwrap();
+        assert_eq!(storage.load(key).unwrap(), Some(dialogue.clone()));
+        storage.remove(key).unwrap();
+        assert_eq!(storage.load(key).unwrap(), None);
+
+        storage.store(key, dialogue).unwrap();
+        assert_eq!(storage.remove_updated_before(42).unwrap(), 0);
+        assert_eq!(storage.remove_updated_before(43).unwrap(), 1);
+        assert_eq!(storage.load(key).unwrap(), None);
+    }
+}
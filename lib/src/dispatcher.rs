@@ -0,0 +1,476 @@
+use std::error;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::{future, Future, FutureExt, Stream, StreamExt};
+
+use serde::de::DeserializeOwned;
+
+use telegram_bot_raw::{AllowedUpdate, CallbackQuery, Message, Update, UpdateKind};
+
+use crate::api::Api;
+use crate::callback::{CallbackCodec, CallbackDataError};
+use crate::errors::Error;
+use crate::filter::Filter;
+use crate::stream::Acknowledger;
+
+const DEFAULT_CONCURRENCY: usize = 1;
+
+/// Error returned by handlers, any error can be returned with `?`.
+pub type HandlerError = Box<dyn error::Error + Send + Sync>;
+
+/// Result of handlers.
+pub type HandlerResult = Result<(), HandlerError>;
+
+type HandlerFuture = Pin<Box<dyn Future<Output = HandlerResult> + Send>>;
+type Handler<S> = Box<dyn Fn(Api, Arc<S>, Update) -> HandlerFuture + Send + Sync>;
+/// Returns the handler of the update if the route matches it.
+type Route<S> = Box<dyn Fn(&Update) -> Option<Matched<S>> + Send + Sync>;
+/// Handler of a matched update, keeps whatever was decoded while matching.
+type Matched<S> = Box<dyn FnOnce(Api, Arc<S>, Update) -> HandlerFuture + Send>;
+type Before<S> = Box<dyn Fn(&Api, &S, &Update) -> bool + Send + Sync>;
+type After<S> = Box<dyn Fn(&Api, &S, &Update, Result<(), &HandlerError>) + Send + Sync>;
+type ErrorHook = Box<dyn Fn(HandlerError) + Send + Sync>;
+
+/// Routes updates from an `UpdatesStream`, a `Webhook` or any other update source
+/// to async handlers.
+///
+/// Every update goes to the first handler registered for it, or to the
+/// [`fallback`](#method.fallback) handler. Handlers get the `Api` and the shared state
+/// of the dispatcher. Errors of handlers and of the update source are passed to the
+/// [error hook](#method.on_error) and don't stop the dispatcher.
+///
+/// ```rust
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use telegram_bot::{Api, Dispatcher, HandlerResult, prelude::*};
+/// #
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let api = Api::new("token");
+/// # if false {
+/// let mut dispatcher = Dispatcher::new(&api, AtomicUsize::new(0));
+/// dispatcher
+///     .on_command("start", |api, counter, message| async move {
+///         counter.fetch_add(1, Ordering::SeqCst);
+///         api.send(message.text_reply("Hi!")).await?;
+///         HandlerResult::Ok(())
+///     })
+///     .on_error(|error| eprintln!("{}", error));
+///
+/// dispatcher.run(api.stream()).await;
+/// # }
+/// # }
+/// ```
+pub struct Dispatcher<S> {
+    api: Api,
+    state: Arc<S>,
+    routes: Vec<Route<S>>,
+    fallback: Option<Handler<S>>,
+    before: Vec<Before<S>>,
+    after: Vec<After<S>>,
+    error_hook: ErrorHook,
+    concurrency: usize,
+    acknowledger: Option<Acknowledger>,
+}
+
+impl<S: Send + Sync + 'static> Dispatcher<S> {
+    /// Create a new `Dispatcher` sharing `state` with all handlers.
+    pub fn new(api: &Api, state: S) -> Self {
+        Dispatcher {
+            api: api.clone(),
+            state: Arc::new(state),
+            routes: Vec::new(),
+            fallback: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            error_hook: Box::new(|error| tracing::error!(error = %error, "handler error")),
+            concurrency: DEFAULT_CONCURRENCY,
+            acknowledger: None,
+        }
+    }
+
+    /// Handle updates of `kind`.
+    pub fn on<F, Fut>(&mut self, kind: AllowedUpdate, handler: F) -> &mut Self
+    where
+        F: Fn(Api, Arc<S>, Update) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        self.route(
+            move |update| update_kind(&update.kind) == Some(kind.clone()),
+            boxed(handler),
+        )
+    }
+
+    /// Handle new messages with the bot command `name`, e.g. `"start"` for `/start`.
+    pub fn on_command<F, Fut>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        F: Fn(Api, Arc<S>, Message) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        self.on_message(Filter::command(name), handler)
+    }
+
+    /// Handle new messages matched by `filter`.
+    pub fn on_message<F, Fut>(&mut self, filter: Filter, handler: F) -> &mut Self
+    where
+        F: Fn(Api, Arc<S>, Message) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        let matches = move |update: &Update| match update.kind {
+            UpdateKind::Message(ref message) => filter.matches(message),
+            _ => false,
+        };
+        let handler = move |api, state, update: Update| match update.kind {
+            UpdateKind::Message(message) => handler(api, state, message).boxed(),
+            _ => future::ok(()).boxed(),
+        };
+        self.route(matches, Box::new(handler))
+    }
+
+    /// Handle callback queries whose data was encoded by `codec` from the enum variant
+    /// `variant` of `T`, e.g. `"Vote"` for `Action::Vote { .. }`.
+    ///
+    /// Callback queries with forged data don't match and go to the fallback handler.
+    pub fn on_callback<T, F, Fut>(
+        &mut self,
+        codec: &CallbackCodec,
+        variant: &str,
+        handler: F,
+    ) -> &mut Self
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(Api, Arc<S>, CallbackQuery, T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        let (codec, variant) = (codec.clone(), variant.to_string());
+        let handler = Arc::new(handler);
+        // The data is verified and parsed once, the matched variant is deserialized from it.
+        let route = move |update: &Update| {
+            let value = match update.kind {
+                UpdateKind::CallbackQuery(CallbackQuery {
+                    data: Some(ref data),
+                    ..
+                }) => match codec.decode_variant(data) {
+                    Ok((name, value)) if name == variant => value,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let handler = handler.clone();
+            let matched: Matched<S> = Box::new(move |api, state, update| match update.kind {
+                UpdateKind::CallbackQuery(query) => match serde_json::from_value::<T>(value) {
+                    Ok(data) => handler(api, state, query, data).boxed(),
+                    Err(error) => future::err(CallbackDataError::Json(error).into()).boxed(),
+                },
+                _ => future::ok(()).boxed(),
+            });
+            Some(matched)
+        };
+        self.routes.push(Box::new(route));
+        self
+    }
+
+    /// Handle updates no other handler was registered for.
+    pub fn fallback<F, Fut>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(Api, Arc<S>, Update) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        self.fallback = Some(boxed(handler));
+        self
+    }
+
+    /// Run `middleware` before the handler of every update,
+    /// the update is dropped if it returns `false`.
+    pub fn before<F>(&mut self, middleware: F) -> &mut Self
+    where
+        F: Fn(&Api, &S, &Update) -> bool + Send + Sync + 'static,
+    {
+        self.before.push(Box::new(middleware));
+        self
+    }
+
+    /// Run `middleware` after the handler of every update with the result of the handler.
+    pub fn after<F>(&mut self, middleware: F) -> &mut Self
+    where
+        F: Fn(&Api, &S, &Update, Result<(), &HandlerError>) + Send + Sync + 'static,
+    {
+        self.after.push(Box::new(middleware));
+        self
+    }
+
+    /// Set the hook receiving errors of handlers and of the update source.
+    ///
+    /// By default errors are logged with `tracing`.
+    pub fn on_error<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(HandlerError) + Send + Sync + 'static,
+    {
+        self.error_hook = Box::new(hook);
+        self
+    }
+
+    /// Set the maximal number of updates handled at the same time.
+    ///
+    /// Defaults to 1, so updates are handled in order.
+    pub fn concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Acknowledge every update with `acknowledger` once it is handled, an `UpdatesStream`
+    /// in [ack mode](struct.UpdatesStream.html#method.ack_mode) stops requesting new updates
+    /// until the received ones are acknowledged.
+    ///
+    /// Updates are also acknowledged when their handler fails, when they are dropped
+    /// by middleware and when there is no handler for them.
+    pub fn acknowledge(&mut self, acknowledger: Acknowledger) -> &mut Self {
+        self.acknowledger = Some(acknowledger);
+        self
+    }
+
+    /// Handle all updates of `updates` until it ends.
+    ///
+    /// Updates of a stream in ack mode are only acknowledged with an
+    /// [`acknowledge`](#method.acknowledge)r.
+    pub async fn run<U>(&self, updates: U)
+    where
+        U: Stream<Item = Result<Update, Error>>,
+    {
+        updates
+            .for_each_concurrent(self.concurrency, |update| async move {
+                match update {
+                    Ok(update) => self.dispatch(update).await,
+                    Err(error) => (self.error_hook)(error.into()),
+                }
+            })
+            .await
+    }
+
+    /// Handle a single update.
+    pub async fn dispatch(&self, update: Update) {
+        let update_id = update.id;
+        self.handle(update).await;
+        if let Some(ref acknowledger) = self.acknowledger {
+            if let Err(error) = acknowledger.ack(update_id) {
+                (self.error_hook)(error.into());
+            }
+        }
+    }
+
+    async fn handle(&self, update: Update) {
+        if !self
+            .before
+            .iter()
+            .all(|before| before(&self.api, &self.state, &update))
+        {
+            tracing::trace!(update_id = update.id, "update dropped by middleware");
+            return;
+        }
+
+        let matched = self.routes.iter().find_map(|route| route(&update));
+        let handler = match (matched, &self.fallback) {
+            (Some(matched), _) => matched,
+            (None, Some(fallback)) => Box::new(fallback),
+            (None, None) => {
+                tracing::trace!(update_id = update.id, "no handler for update");
+                return;
+            }
+        };
+
+        let handled = if self.after.is_empty() {
+            None
+        } else {
+            Some(update.clone())
+        };
+        let result = handler(self.api.clone(), self.state.clone(), update).await;
+
+        if let Some(update) = handled {
+            for after in &self.after {
+                after(&self.api, &self.state, &update, result.as_ref().map(|_| ()));
+            }
+        }
+        if let Err(error) = result {
+            (self.error_hook)(error);
+        }
+    }
+
+    fn route<M>(&mut self, matches: M, handler: Handler<S>) -> &mut Self
+    where
+        M: Fn(&Update) -> bool + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.routes.push(Box::new(move |update| {
+            if !matches(update) {
+                return None;
+            }
+            let handler = handler.clone();
+            let matched: Matched<S> =
+                Box::new(move |api, state, update| handler(api, state, update));
+            Some(matched)
+        }));
+        self
+    }
+}
+
+impl<S> fmt::Debug for Dispatcher<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("routes", &self.routes.len())
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+fn boxed<S, F, Fut>(handler: F) -> Handler<S>
+where
+    F: Fn(Api, Arc<S>, Update) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = HandlerResult> + Send + 'static,
+{
+    Box::new(move |api, state, update| handler(api, state, update).boxed())
+}
+
+fn update_kind(kind: &UpdateKind) -> Option<AllowedUpdate> {
+    match kind {
+        UpdateKind::Message(_) => Some(AllowedUpdate::Message),
+        UpdateKind::EditedMessage(_) => Some(AllowedUpdate::EditedMessage),
+        UpdateKind::ChannelPost(_) => Some(AllowedUpdate::ChannelPost),
+        UpdateKind::EditedChannelPost(_) => Some(AllowedUpdate::EditedChannelPost),
+        UpdateKind::InlineQuery(_) => Some(AllowedUpdate::InlineQuery),
+        UpdateKind::CallbackQuery(_) => Some(AllowedUpdate::CallbackQuery),
+        UpdateKind::Error(_) | UpdateKind::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use std::time::Duration;
+
+    use futures::stream;
+
+    use super::*;
+    use crate::errors::ErrorKind;
+    use crate::runtime::{default_runtime, timeout};
+    use crate::test_support::{message_update, updates_api};
+
+    fn update(id: i64, text: &str) -> Result<Update, Error> {
+        Ok(serde_json::from_str(&message_update(id, text)).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher() {
+        let api = Api::new("token");
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new(&api, log.clone());
+        let errors = log.clone();
+        let after = log.clone();
+        dispatcher
+            .on_command("start", |_, log, message| async move {
+                log.lock().unwrap().push(format!("start {}", message.id));
+                Ok(())
+            })
+            .on_command("fail", |_, _, _| async { Err("failed".into()) })
+            .fallback(|_, log, update| async move {
+                log.lock().unwrap().push(format!("fallback {}", update.id));
+                Ok(())
+            })
+            .before(|_, _, update| update.id != 4)
+            .after(move |_, _, update, result| {
+                after
+                    .lock()
+                    .unwrap()
+                    .push(format!("after {} {}", update.id, result.is_ok()));
+            })
+            .on_error(move |error| errors.lock().unwrap().push(error.to_string()));
+
+        let source_error = Err(ErrorKind::FileUnavailable.into());
+        let updates = vec![
+            update(1, "/start"),
+            update(2, "hello"),
+            update(3, "/fail"),
+            update(4, "/start"),
+            source_error,
+        ];
+        dispatcher.run(stream::iter(updates)).await;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "start 1",
+                "after 1 true",
+                "fallback 2",
+                "after 2 true",
+                "after 3 false",
+                "failed",
+                "file is not available for download",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge() {
+        let (api, requests) = updates_api(&[message_update(1, "hi"), message_update(2, "/start")]);
+        let mut updates = api.stream();
+        updates.ack_mode(true);
+        let mut dispatcher = Dispatcher::new(&api, ());
+        dispatcher
+            .acknowledge(updates.acknowledger())
+            .on_command("start", |_, _, _| async { Err("failed".into()) })
+            .on_error(|_| ());
+
+        // The third update is only requested once both others are acknowledged.
+        let run = dispatcher.run(updates.take(3));
+        assert!(timeout(&*default_runtime(), Duration::from_secs(5), run)
+            .await
+            .is_some());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(r#""offset":3"#));
+    }
+
+    #[tokio::test]
+    async fn test_callback_routing() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        enum Action {
+            Vote(u32),
+            Cancel,
+        }
+
+        let codec = CallbackCodec::with_key("secret");
+        let callback = |id: i64, data: &str| -> Update {
+            let json = format!(
+                r#"{{"update_id":{},"callback_query":{{"id":"q","chat_instance":"c",
+                    "from":{{"id":1,"is_bot":false,"first_name":"A"}},"data":{:?}}}}}"#,
+                id, data
+            );
+            serde_json::from_str(&json).unwrap()
+        };
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new(&Api::new("token"), log.clone());
+        dispatcher
+            .on_callback(&codec, "Vote", |_, log, _, action| async move {
+                if let Action::Vote(option) = action {
+                    log.lock().unwrap().push(format!("vote {}", option));
+                }
+                Ok(())
+            })
+            .fallback(|_, log, update| async move {
+                log.lock().unwrap().push(format!("fallback {}", update.id));
+                Ok(())
+            });
+
+        let vote = codec.encode(&Action::Vote(2)).unwrap();
+        dispatcher.dispatch(callback(1, &vote)).await;
+        dispatcher
+            .dispatch(callback(2, &codec.encode(&Action::Cancel).unwrap()))
+            .await;
+        dispatcher
+            .dispatch(callback(3, &vote.replace("2", "3")))
+            .await;
+        assert_eq!(*log.lock().unwrap(), ["vote 2", "fallback 2", "fallback 3"]);
+    }
+}
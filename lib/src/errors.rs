@@ -1,7 +1,10 @@
 use std::error;
 use std::fmt;
+use std::io;
+use std::time::Duration;
 
 use telegram_bot_raw;
+use telegram_bot_raw::{ErrorCategory, Integer, SupergroupId};
 
 #[derive(Debug)]
 pub struct Error(ErrorKind);
@@ -12,7 +15,100 @@ pub(crate) enum ErrorKind {
     Hyper(hyper::Error),
     Http(hyper::http::Error),
     Io(std::io::Error),
+    Json(serde_json::Error),
     InvalidMultipartFilename,
+    UnmatchedRequest(&'static str),
+    Timeout(Duration),
+    InvalidProxy(String),
+    HttpStatus(u16),
+    FileUnavailable,
+    FileTooLarge(u64),
+    Storage(Box<dyn error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Returns the classification of the error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use telegram_bot::{Api, ChatId, ErrorCategory, prelude::*};
+    /// #
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let api = Api::new("token");
+    /// # if false {
+    /// let chat = ChatId::new(61031);
+    /// match api.send(chat.text("Message")).await {
+    ///     Ok(_) => (),
+    ///     Err(ref error) if error.category() == ErrorCategory::Forbidden => {
+    ///         println!("bot was blocked by the user")
+    ///     }
+    ///     Err(error) => println!("{}", error),
+    /// }
+    /// # }
+    /// # }
+    /// ```
+    pub fn category(&self) -> ErrorCategory {
+        match &self.0 {
+            ErrorKind::Raw(error) => error.category(),
+            ErrorKind::Hyper(error) if error.is_timeout() => ErrorCategory::Timeout,
+            ErrorKind::Hyper(error) if error.is_parse() => ErrorCategory::Http,
+            ErrorKind::Hyper(_) => ErrorCategory::Network,
+            ErrorKind::Http(_) => ErrorCategory::Http,
+            ErrorKind::Io(error) => match error.kind() {
+                io::ErrorKind::TimedOut => ErrorCategory::Timeout,
+                io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof => ErrorCategory::Network,
+                _ => ErrorCategory::Other,
+            },
+            ErrorKind::Json(_) => ErrorCategory::Json,
+            ErrorKind::InvalidMultipartFilename => ErrorCategory::Other,
+            ErrorKind::UnmatchedRequest(_) => ErrorCategory::Other,
+            ErrorKind::Timeout(_) => ErrorCategory::Timeout,
+            ErrorKind::InvalidProxy(_) => ErrorCategory::Other,
+            ErrorKind::HttpStatus(401) => ErrorCategory::Unauthorized,
+            ErrorKind::HttpStatus(403) => ErrorCategory::Forbidden,
+            ErrorKind::HttpStatus(404) => ErrorCategory::NotFound,
+            ErrorKind::HttpStatus(_) => ErrorCategory::Http,
+            ErrorKind::FileUnavailable => ErrorCategory::Other,
+            ErrorKind::FileTooLarge(_) => ErrorCategory::Other,
+            ErrorKind::Storage(_) => ErrorCategory::Other,
+        }
+    }
+
+    /// Returns the numeric error code returned by the Telegram server.
+    pub fn error_code(&self) -> Option<Integer> {
+        match &self.0 {
+            ErrorKind::Raw(error) => error.error_code(),
+            ErrorKind::HttpStatus(status) => Some(Integer::from(*status)),
+            _ => None,
+        }
+    }
+
+    /// Returns the time left to wait before the request can be repeated,
+    /// if the request was rejected by the flood control.
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match &self.0 {
+            ErrorKind::Raw(error) => error
+                .retry_after()
+                .map(|seconds| Duration::from_secs(seconds.max(0) as u64)),
+            _ => None,
+        }
+    }
+
+    /// Returns the supergroup the group has been migrated to,
+    /// if the request was rejected because of the migration.
+    pub(crate) fn migrate_to_chat_id(&self) -> Option<SupergroupId> {
+        match &self.0 {
+            ErrorKind::Raw(error) => error.migrate_to_chat_id().map(SupergroupId::new),
+            _ => None,
+        }
+    }
 }
 
 impl From<telegram_bot_raw::Error> for ErrorKind {
@@ -39,6 +135,12 @@ impl From<std::io::Error> for ErrorKind {
     }
 }
 
+impl From<serde_json::Error> for ErrorKind {
+    fn from(error: serde_json::Error) -> Self {
+        ErrorKind::Json(error)
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
         Error(kind)
@@ -52,7 +154,19 @@ impl fmt::Display for Error {
             ErrorKind::Hyper(error) => write!(f, "{}", error),
             ErrorKind::Http(error) => write!(f, "{}", error),
             ErrorKind::Io(error) => write!(f, "{}", error),
+            ErrorKind::Json(error) => write!(f, "{}", error),
             ErrorKind::InvalidMultipartFilename => write!(f, "invalid multipart filename"),
+            ErrorKind::UnmatchedRequest(method) => {
+                write!(f, "no recorded interaction matches {} request", method)
+            }
+            ErrorKind::Timeout(duration) => write!(f, "request timed out after {:?}", duration),
+            ErrorKind::InvalidProxy(url) => write!(f, "invalid proxy url: {}", url),
+            ErrorKind::HttpStatus(status) => write!(f, "unexpected http status {}", status),
+            ErrorKind::FileUnavailable => write!(f, "file is not available for download"),
+            ErrorKind::FileTooLarge(limit) => {
+                write!(f, "file is larger than the limit of {} bytes", limit)
+            }
+            ErrorKind::Storage(error) => write!(f, "storage error: {}", error),
         }
     }
 }
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::ops::Not;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use telegram_bot_raw::{Message, MessageChat, MessageKind, UserId};
+
+use crate::util::messages::MessageText;
+
+/// Composable predicate over messages, see [`UpdateView::matching`].
+///
+/// ```rust
+/// # use telegram_bot::{Filter, MessageKind};
+/// let photo = Filter::kind(|kind| matches!(kind, MessageKind::Photo { .. }));
+/// let filter = Filter::private()
+///     .and(Filter::command("start").or(photo))
+///     .and(!Filter::from_user(42.into()));
+/// ```
+///
+/// [`UpdateView::matching`]: struct.UpdateView.html#method.matching
+#[derive(Clone)]
+pub struct Filter {
+    predicate: Arc<dyn Fn(&Message) -> bool + Send + Sync>,
+}
+
+impl Filter {
+    /// Create a filter from a custom predicate.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Message) -> bool + Send + Sync + 'static,
+    {
+        Filter {
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Match messages in private chats.
+    pub fn private() -> Self {
+        Self::new(|message| matches!(message.chat, MessageChat::Private(_)))
+    }
+
+    /// Match messages in groups.
+    pub fn group() -> Self {
+        Self::new(|message| matches!(message.chat, MessageChat::Group(_)))
+    }
+
+    /// Match messages in supergroups.
+    pub fn supergroup() -> Self {
+        Self::new(|message| matches!(message.chat, MessageChat::Supergroup(_)))
+    }
+
+    /// Match messages sent by `user`.
+    pub fn from_user(user: UserId) -> Self {
+        Self::new(move |message| message.from.id == user)
+    }
+
+    /// Match messages whose kind satisfies `predicate`.
+    pub fn kind<F>(predicate: F) -> Self
+    where
+        F: Fn(&MessageKind) -> bool + Send + Sync + 'static,
+    {
+        Self::new(move |message| predicate(&message.kind))
+    }
+
+    /// Match messages whose text or caption matches `regex`.
+    pub fn regex(regex: Regex) -> Self {
+        Self::new(move |message| match message.text() {
+            Some(text) => regex.is_match(&text),
+            None => false,
+        })
+    }
+
+    /// Match the bot command `name`, e.g. `"start"` matches `/start` and `/start@bot`.
+    pub fn command<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        Self::new(move |message| command(message) == Some(&name))
+    }
+
+    /// Match messages matched by both filters.
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(move |message| self.matches(message) && other.matches(message))
+    }
+
+    /// Match messages matched by either filter.
+    pub fn or(self, other: Filter) -> Self {
+        Self::new(move |message| self.matches(message) || other.matches(message))
+    }
+
+    /// Returns `true` if `message` matches this filter.
+    pub fn matches(&self, message: &Message) -> bool {
+        (self.predicate)(message)
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Self::new(move |message| !self.matches(message))
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Filter").finish()
+    }
+}
+
+/// Returns the name of the bot command the message starts with, without the bot username.
+pub(crate) fn command(message: &Message) -> Option<&str> {
+    let text = match message.kind {
+        MessageKind::Text { ref data, .. } => data,
+        _ => return None,
+    };
+    if !text.starts_with('/') {
+        return None;
+    }
+    let command = text[1..].split_whitespace().next().unwrap_or("");
+    command.split('@').next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(chat: &str, from: i64, text: &str) -> Message {
+        let json = format!(
+            r#"{{"message_id":1,"date":0,"chat":{},"from":{{"id":{},"is_bot":false,
+                "first_name":"A"}},"text":{:?}}}"#,
+            chat, from, text
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        let private = r#"{"id":1,"type":"private","first_name":"A"}"#;
+        let group =
+            r#"{"id":-1,"type":"group","title":"G","all_members_are_administrators":false}"#;
+
+        let start = Filter::command("start");
+        assert!(start.matches(&message(private, 1, "/start")));
+        assert!(start.matches(&message(private, 1, "/start@bot now")));
+        assert!(!start.matches(&message(private, 1, "/starting")));
+        assert!(!start.matches(&message(private, 1, "start")));
+
+        let filter = Filter::group()
+            .and(!Filter::from_user(2.into()))
+            .or(Filter::regex(Regex::new("^he+y$").unwrap()));
+        assert!(filter.matches(&message(group, 1, "hi")));
+        assert!(!filter.matches(&message(group, 2, "hi")));
+        assert!(!filter.matches(&message(private, 1, "hi")));
+        assert!(filter.matches(&message(private, 2, "heeey")));
+    }
+}
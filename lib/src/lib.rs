@@ -2,17 +2,51 @@
 //! See [readme](https://github.com/telegram-rs/telegram-bot) for details.
 
 mod api;
+mod atomic_file;
+mod bulk;
+mod callback;
+mod dispatcher;
 mod errors;
+mod filter;
+mod limiter;
 mod macros;
+mod migration;
+mod offset;
+mod pool;
+mod retry;
 mod stream;
+#[cfg(test)]
+mod test_support;
+mod view;
+mod webhook;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod commands;
 pub mod connector;
+pub mod dialogue;
+pub mod metrics;
 pub mod prelude;
+pub mod runtime;
 pub mod types;
 pub mod util;
 
-pub use self::api::Api;
+pub use self::api::{Api, ApiBuilder};
+pub use self::bulk::{CancelHandle, SendAll};
+pub use self::callback::{CallbackCodec, CallbackDataError};
+pub use self::commands::{BotCommands, ParseCommandError};
+pub use self::dispatcher::{Dispatcher, HandlerError, HandlerResult};
 pub use self::errors::Error;
+pub use self::filter::Filter;
+pub use self::limiter::RateLimiter;
+pub use self::migration::{MemoryMigrationStore, MigrationStore};
+pub use self::offset::{FileOffsetStore, MemoryOffsetStore, OffsetStore};
+pub use self::pool::{ApiPool, BotStats};
+pub use self::retry::RetryPolicy;
 pub use prelude::*;
-pub use stream::UpdatesStream;
+#[cfg(feature = "macros")]
+pub use telegram_bot_macros::BotCommands;
+pub use stream::{Acknowledger, Health, HealthMonitor, UpdatesStream};
+pub use view::UpdateView;
+pub use webhook::Webhook;
 pub use types::*;
@@ -0,0 +1,341 @@
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use telegram_bot_raw::ChatRef;
+
+use crate::errors::Error;
+use crate::runtime::Runtime;
+
+const TELEGRAM_GLOBAL_MESSAGES_PER_SECOND: u32 = 30;
+const TELEGRAM_PRIVATE_CHAT_MESSAGES_PER_SECOND: u32 = 1;
+const TELEGRAM_GROUP_CHAT_MESSAGES_PER_MINUTE: u32 = 20;
+
+const MAX_LEARNED_INTERVAL_SECONDS: u64 = 60;
+/// Number of successful requests after which a learned interval is halved again.
+const LEARNED_INTERVAL_DECAY_SUCCESSES: u32 = 20;
+const MAX_TRACKED_CHATS: usize = 10_000;
+
+/// Outbound rate limiter which queues requests to stay within
+/// the [Telegram limits](https://core.telegram.org/bots/faq#my-bot-is-hitting-limits-how-do-i-avoid-this).
+///
+/// Every request with a `chat_id` parameter has to pass both the global bucket
+/// and the bucket of its chat, requests without a target chat are not limited.
+/// When the server still answers with `retry_after`, the chat is paused for
+/// that duration and its rate is halved. After 20 successful requests in a row
+/// the rate is doubled again, until it's back at the configured rate.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telegram_bot::{Api, RateLimiter};
+///
+/// let mut limiter = RateLimiter::new();
+/// limiter.global(20, Duration::from_secs(1));
+///
+/// let api = Api::builder("token").rate_limiter(limiter).build();
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    global: Rate,
+    private_chat: Rate,
+    group_chat: Rate,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rate {
+    burst: u32,
+    interval: Duration,
+}
+
+/// Token bucket tracked as a theoretical arrival time of the next request.
+#[derive(Debug)]
+struct Bucket {
+    arrival: Instant,
+    learned_interval: Option<Duration>,
+    successes: u32,
+}
+
+#[derive(Debug)]
+struct State {
+    global: Bucket,
+    chats: HashMap<ChatRef, Bucket>,
+}
+
+impl RateLimiter {
+    /// Create a new `RateLimiter` with the default Telegram limits: 30 messages per second
+    /// overall, 1 message per second in a private chat and 20 messages per minute in a group.
+    pub fn new() -> Self {
+        RateLimiter {
+            global: Rate::new(TELEGRAM_GLOBAL_MESSAGES_PER_SECOND, Duration::from_secs(1)),
+            private_chat: Rate::new(
+                TELEGRAM_PRIVATE_CHAT_MESSAGES_PER_SECOND,
+                Duration::from_secs(1),
+            ),
+            group_chat: Rate::new(
+                TELEGRAM_GROUP_CHAT_MESSAGES_PER_MINUTE,
+                Duration::from_secs(60),
+            ),
+            state: Mutex::new(State {
+                global: Bucket::new(Instant::now()),
+                chats: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Set the number of requests allowed per `period` across all chats.
+    pub fn global(&mut self, count: u32, period: Duration) -> &mut Self {
+        self.global = Rate::new(count, period);
+        self
+    }
+
+    /// Set the number of requests allowed per `period` in a single private chat.
+    pub fn private_chat(&mut self, count: u32, period: Duration) -> &mut Self {
+        self.private_chat = Rate::new(count, period);
+        self
+    }
+
+    /// Set the number of requests allowed per `period` in a single group,
+    /// supergroup or channel.
+    pub fn group_chat(&mut self, count: u32, period: Duration) -> &mut Self {
+        self.group_chat = Rate::new(count, period);
+        self
+    }
+
+    /// Wait until a request to `chat` is allowed to be sent.
+    ///
+    /// The chat slot is reserved first and the global one only once the chat
+    /// is ready, so a backlog in a single chat doesn't hold up other chats.
+    pub(crate) async fn acquire(&self, chat: &ChatRef, runtime: &dyn Runtime) {
+        let delay = self.reserve_chat(chat, Instant::now());
+        if delay > Duration::from_secs(0) {
+            tracing::trace!(chat = %chat, delay = ?delay, "request delayed by chat rate limit");
+            runtime.sleep(delay).await;
+        }
+
+        let delay = self.reserve_global(Instant::now());
+        if delay > Duration::from_secs(0) {
+            tracing::trace!(chat = %chat, delay = ?delay, "request delayed by global rate limit");
+            runtime.sleep(delay).await;
+        }
+    }
+
+    /// Pause and slow down `chat` if `error` was caused by the flood control.
+    pub(crate) fn learn(&self, chat: &ChatRef, error: &Error) {
+        if let Some(retry_after) = error.retry_after() {
+            self.pause(chat, retry_after, Instant::now());
+        }
+    }
+
+    /// Relax the learned rate of `chat` after a successful request.
+    pub(crate) fn succeeded(&self, chat: &ChatRef) {
+        let kind_rate = self.chat_rate(chat);
+        let mut state = self.state.lock().unwrap();
+        let bucket = match state.chats.get_mut(chat) {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        let interval = match bucket.learned_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        bucket.successes += 1;
+        if bucket.successes < LEARNED_INTERVAL_DECAY_SUCCESSES {
+            return;
+        }
+        bucket.successes = 0;
+        let interval = interval / 2;
+        bucket.learned_interval = if interval > kind_rate.interval {
+            Some(interval)
+        } else {
+            None
+        };
+        tracing::debug!(
+            chat = %chat,
+            interval = ?bucket.rate(kind_rate).interval,
+            "rate limit relaxed after successful requests"
+        );
+    }
+
+    fn pause(&self, chat: &ChatRef, retry_after: Duration, now: Instant) {
+        let kind_rate = self.chat_rate(chat);
+        let mut state = self.state.lock().unwrap();
+        let bucket = state
+            .chats
+            .entry(chat.clone())
+            .or_insert_with(|| Bucket::new(now));
+
+        let interval = bucket.learned_interval.unwrap_or(kind_rate.interval);
+        let interval = min(
+            interval * 2,
+            Duration::from_secs(MAX_LEARNED_INTERVAL_SECONDS),
+        );
+        bucket.learned_interval = Some(max(interval, kind_rate.interval));
+        bucket.successes = 0;
+
+        let rate = bucket.rate(kind_rate);
+        bucket.arrival = max(bucket.arrival, now + retry_after + rate.tolerance());
+        tracing::warn!(
+            chat = %chat,
+            retry_after = ?retry_after,
+            interval = ?rate.interval,
+            "rate limit tightened after flood control error"
+        );
+    }
+
+    fn reserve_chat(&self, chat: &ChatRef, now: Instant) -> Duration {
+        let kind_rate = self.chat_rate(chat);
+        let mut state = self.state.lock().unwrap();
+        if state.chats.len() > MAX_TRACKED_CHATS {
+            // Learned rates are kept for a while after the last request to the chat.
+            let learned_for = Duration::from_secs(MAX_LEARNED_INTERVAL_SECONDS);
+            state
+                .chats
+                .retain(|_, bucket| match bucket.learned_interval {
+                    Some(_) => bucket.arrival + learned_for > now,
+                    None => bucket.arrival > now,
+                });
+        }
+
+        let bucket = state
+            .chats
+            .entry(chat.clone())
+            .or_insert_with(|| Bucket::new(now));
+        bucket.reserve(bucket.rate(kind_rate), now)
+    }
+
+    fn reserve_global(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        state.global.reserve(self.global, now)
+    }
+
+    fn chat_rate(&self, chat: &ChatRef) -> Rate {
+        match chat {
+            ChatRef::Id(id) if i64::from(*id) > 0 => self.private_chat,
+            _ => self.group_chat,
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new()
+    }
+}
+
+impl Rate {
+    fn new(count: u32, period: Duration) -> Self {
+        let count = max(count, 1);
+        Rate {
+            burst: count,
+            interval: period / count,
+        }
+    }
+
+    fn tolerance(&self) -> Duration {
+        self.interval * (self.burst - 1)
+    }
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Self {
+        Bucket {
+            arrival: now,
+            learned_interval: None,
+            successes: 0,
+        }
+    }
+
+    fn rate(&self, rate: Rate) -> Rate {
+        Rate {
+            burst: rate.burst,
+            interval: self.learned_interval.unwrap_or(rate.interval),
+        }
+    }
+
+    fn reserve(&mut self, rate: Rate, now: Instant) -> Duration {
+        let tolerance = rate.tolerance();
+        let send_at = if self.arrival <= now + tolerance {
+            now
+        } else {
+            self.arrival - tolerance
+        };
+        self.arrival = max(self.arrival, send_at) + rate.interval;
+        send_at - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{ChatId, Request, SendMessage, ToChatRef};
+
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new();
+        let now = Instant::now();
+        let zero = Duration::from_secs(0);
+
+        let private = SendMessage::new(ChatId::new(42), "text")
+            .serialize()
+            .unwrap()
+            .chat_ref()
+            .unwrap();
+        assert_eq!(limiter.reserve_chat(&private, now), zero);
+        assert_eq!(limiter.reserve_chat(&private, now), Duration::from_secs(1));
+        assert_eq!(limiter.reserve_chat(&private, now), Duration::from_secs(2));
+
+        let group = ChatId::new(-42).to_chat_ref();
+        for _ in 0..20 {
+            assert_eq!(limiter.reserve_chat(&group, now), zero);
+        }
+        assert_eq!(limiter.reserve_chat(&group, now), Duration::from_secs(3));
+
+        for _ in 0..30 {
+            assert_eq!(limiter.reserve_global(now), zero);
+        }
+        assert!(limiter.reserve_global(now) > zero);
+    }
+
+    #[test]
+    fn test_learned_interval() {
+        let limiter = RateLimiter::new();
+        let now = Instant::now();
+        let interval = |chat| {
+            let state = limiter.state.lock().unwrap();
+            state
+                .chats
+                .get(chat)
+                .and_then(|bucket: &Bucket| bucket.learned_interval)
+        };
+
+        let chat = ChatId::new(42).to_chat_ref();
+        limiter.pause(&chat, Duration::from_secs(5), now);
+        limiter.pause(&chat, Duration::from_secs(5), now);
+        assert_eq!(interval(&chat), Some(Duration::from_secs(4)));
+        assert_eq!(limiter.reserve_chat(&chat, now), Duration::from_secs(5));
+
+        for _ in 0..LEARNED_INTERVAL_DECAY_SUCCESSES {
+            limiter.succeeded(&chat);
+        }
+        assert_eq!(interval(&chat), Some(Duration::from_secs(2)));
+        for _ in 0..LEARNED_INTERVAL_DECAY_SUCCESSES {
+            limiter.succeeded(&chat);
+        }
+        assert_eq!(interval(&chat), None);
+
+        limiter.pause(&chat, Duration::from_secs(5), now);
+        for id in 0..MAX_TRACKED_CHATS as i64 {
+            limiter.reserve_chat(&ChatId::new(1000 + id).to_chat_ref(), now);
+        }
+        let later = now + Duration::from_secs(10 + MAX_LEARNED_INTERVAL_SECONDS);
+        limiter.reserve_chat(&ChatId::new(1).to_chat_ref(), later);
+        assert_eq!(interval(&chat), None);
+        assert_eq!(limiter.state.lock().unwrap().chats.len(), 1);
+    }
+}
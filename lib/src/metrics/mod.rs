@@ -0,0 +1,72 @@
+//! Hooks for collecting metrics of requests and updates.
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus::PrometheusMetrics;
+
+use std::cmp::max;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use telegram_bot_raw::{Integer, Update, UpdateKind};
+
+use crate::errors::Error;
+
+/// Receives measurements of an `Api` instance and its update streams.
+///
+/// All methods do nothing by default, implement the ones you are interested in
+/// and pass the implementation to [`ApiBuilder::metrics`].
+///
+/// [`ApiBuilder::metrics`]: ../struct.ApiBuilder.html#method.metrics
+pub trait Metrics: Send + Sync {
+    /// Called when a request to the Bot API `method` finished, retries included.
+    fn request(&self, method: &'static str, elapsed: Duration, result: Result<(), &Error>) {
+        let _ = (method, elapsed, result);
+    }
+
+    /// Called for every update received by an `UpdatesStream`, with the time passed
+    /// since the message of the update was sent, if the update has a message.
+    fn update(&self, kind: &UpdateKind, lag: Option<Duration>) {
+        let _ = (kind, lag);
+    }
+
+    /// Called when a long polling request of an `UpdatesStream` finished,
+    /// with the number of received updates.
+    fn poll(&self, elapsed: Duration, result: Result<usize, &Error>) {
+        let _ = (elapsed, result);
+    }
+}
+
+/// Metrics implementation which discards all measurements, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Returns the name of the kind of update, as used by `allowed_updates`.
+pub fn update_kind_name(kind: &UpdateKind) -> &'static str {
+    match kind {
+        UpdateKind::Message(_) => "message",
+        UpdateKind::EditedMessage(_) => "edited_message",
+        UpdateKind::ChannelPost(_) => "channel_post",
+        UpdateKind::EditedChannelPost(_) => "edited_channel_post",
+        UpdateKind::InlineQuery(_) => "inline_query",
+        UpdateKind::CallbackQuery(_) => "callback_query",
+        UpdateKind::Error(_) => "error",
+        UpdateKind::Unknown => "unknown",
+    }
+}
+
+/// Returns the time passed since the message of `update` was sent or edited.
+pub(crate) fn update_lag(update: &Update) -> Option<Duration> {
+    let date = match update.kind {
+        UpdateKind::Message(ref message) => message.date,
+        UpdateKind::EditedMessage(ref message) => message.edit_date.unwrap_or(message.date),
+        UpdateKind::ChannelPost(ref post) => post.date,
+        UpdateKind::EditedChannelPost(ref post) => post.edit_date.unwrap_or(post.date),
+        _ => return None,
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as Integer;
+    Some(Duration::from_secs(max(now - date, 0) as u64))
+}
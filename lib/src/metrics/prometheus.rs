@@ -0,0 +1,274 @@
+//! Metrics in the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use telegram_bot_raw::UpdateKind;
+
+use super::{update_kind_name, Metrics};
+use crate::errors::Error;
+
+/// Upper bounds of the histogram buckets in seconds.
+const BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Metrics collector rendering its measurements in the
+/// [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use telegram_bot::Api;
+/// use telegram_bot::metrics::PrometheusMetrics;
+///
+/// let metrics = Arc::new(PrometheusMetrics::new());
+/// let api = Api::builder("token").metrics(metrics.clone()).build();
+///
+/// // Serve this from the `/metrics` endpoint of your bot.
+/// let exposition = metrics.render();
+/// ```
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: BTreeMap<&'static str, u64>,
+    request_errors: BTreeMap<(&'static str, String), u64>,
+    request_duration: BTreeMap<&'static str, Histogram>,
+    updates: BTreeMap<&'static str, u64>,
+    update_lag: Histogram,
+    polls: u64,
+    poll_errors: u64,
+    poll_duration: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl PrometheusMetrics {
+    /// Create a new `PrometheusMetrics` without measurements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render all measurements in the text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "telegram_bot_requests_total",
+            "counter",
+            "Requests sent by method.",
+        );
+        for (method, count) in &state.requests {
+            let _ = writeln!(
+                out,
+                "telegram_bot_requests_total{{method=\"{}\"}} {}",
+                method, count
+            );
+        }
+
+        header(
+            &mut out,
+            "telegram_bot_request_errors_total",
+            "counter",
+            "Failed requests by method and error code.",
+        );
+        for ((method, code), count) in &state.request_errors {
+            let _ = writeln!(
+                out,
+                "telegram_bot_request_errors_total{{method=\"{}\",error_code=\"{}\"}} {}",
+                method, code, count
+            );
+        }
+
+        header(
+            &mut out,
+            "telegram_bot_request_duration_seconds",
+            "histogram",
+            "Duration of requests by method, retries included.",
+        );
+        for (method, histogram) in &state.request_duration {
+            let labels = format!("method=\"{}\"", method);
+            histogram.render(&mut out, "telegram_bot_request_duration_seconds", &labels);
+        }
+
+        header(
+            &mut out,
+            "telegram_bot_updates_total",
+            "counter",
+            "Updates received by kind.",
+        );
+        for (kind, count) in &state.updates {
+            let _ = writeln!(
+                out,
+                "telegram_bot_updates_total{{kind=\"{}\"}} {}",
+                kind, count
+            );
+        }
+
+        header(
+            &mut out,
+            "telegram_bot_update_lag_seconds",
+            "histogram",
+            "Time between sending of a message and receiving its update.",
+        );
+        state
+            .update_lag
+            .render(&mut out, "telegram_bot_update_lag_seconds", "");
+
+        header(
+            &mut out,
+            "telegram_bot_polls_total",
+            "counter",
+            "Long polling requests.",
+        );
+        let _ = writeln!(out, "telegram_bot_polls_total {}", state.polls);
+
+        header(
+            &mut out,
+            "telegram_bot_poll_errors_total",
+            "counter",
+            "Failed long polling requests.",
+        );
+        let _ = writeln!(out, "telegram_bot_poll_errors_total {}", state.poll_errors);
+
+        header(
+            &mut out,
+            "telegram_bot_poll_duration_seconds",
+            "histogram",
+            "Round-trip time of long polling requests.",
+        );
+        state
+            .poll_duration
+            .render(&mut out, "telegram_bot_poll_duration_seconds", "");
+
+        out
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn request(&self, method: &'static str, elapsed: Duration, result: Result<(), &Error>) {
+        let mut state = self.state.lock().unwrap();
+        *state.requests.entry(method).or_insert(0) += 1;
+        state
+            .request_duration
+            .entry(method)
+            .or_default()
+            .observe(elapsed);
+        if let Err(error) = result {
+            let code = match error.error_code() {
+                Some(code) => code.to_string(),
+                None => "none".to_string(),
+            };
+            *state.request_errors.entry((method, code)).or_insert(0) += 1;
+        }
+    }
+
+    fn update(&self, kind: &UpdateKind, lag: Option<Duration>) {
+        let mut state = self.state.lock().unwrap();
+        *state.updates.entry(update_kind_name(kind)).or_insert(0) += 1;
+        if let Some(lag) = lag {
+            state.update_lag.observe(lag);
+        }
+    }
+
+    fn poll(&self, elapsed: Duration, result: Result<usize, &Error>) {
+        let mut state = self.state.lock().unwrap();
+        state.polls += 1;
+        if result.is_err() {
+            state.poll_errors += 1;
+        }
+        state.poll_duration.observe(elapsed);
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS.iter()) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (count, bound) in self.buckets.iter().zip(BUCKETS.iter()) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}{}le=\"{}\"}} {}",
+                name, labels, separator, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+            name, labels, separator, self.count
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{HttpResponse, JsonTrueToUnitResponse, ResponseType};
+
+    use super::*;
+    use crate::errors::ErrorKind;
+
+    #[test]
+    fn test_render() {
+        let metrics = PrometheusMetrics::new();
+        let response = HttpResponse {
+            body: Some(br#"{"ok":false,"error_code":403,"description":"Forbidden"}"#.to_vec()),
+        };
+        let error: Error =
+            ErrorKind::from(JsonTrueToUnitResponse::deserialize(response).unwrap_err()).into();
+
+        metrics.request("sendMessage", Duration::from_millis(200), Ok(()));
+        metrics.request("sendMessage", Duration::from_secs(2), Err(&error));
+        metrics.update(&UpdateKind::Unknown, Some(Duration::from_secs(1)));
+        metrics.poll(Duration::from_secs(5), Ok(1));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("telegram_bot_requests_total{method=\"sendMessage\"} 2\n"));
+        assert!(rendered.contains(
+            "telegram_bot_request_errors_total{method=\"sendMessage\",error_code=\"403\"} 1\n"
+        ));
+        assert!(rendered.contains(
+            "telegram_bot_request_duration_seconds_bucket{method=\"sendMessage\",le=\"0.25\"} 1\n"
+        ));
+        assert!(rendered.contains(
+            "telegram_bot_request_duration_seconds_bucket{method=\"sendMessage\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(rendered.contains("telegram_bot_updates_total{kind=\"unknown\"} 1\n"));
+        assert!(rendered.contains("telegram_bot_update_lag_seconds_count 1\n"));
+        assert!(rendered.contains("telegram_bot_poll_duration_seconds_bucket{le=\"5\"} 1\n"));
+    }
+}
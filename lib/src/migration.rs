@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::RwLock;
+
+use telegram_bot_raw::{
+    ChatRef, GroupId, HttpRequest, MessageChat, MessageKind, SupergroupId, ToChatRef, Update,
+    UpdateKind,
+};
+
+/// Ids of supergroups and channels are below this, ids of basic groups above.
+const MIN_GROUP_ID: i64 = -1_000_000_000_000;
+
+/// Storage for group to supergroup migrations.
+///
+/// Implement this trait to persist the migrations, e.g. to update
+/// chat identifiers stored in your database.
+pub trait MigrationStore: Debug + Send + Sync {
+    /// Returns the supergroup the group has been migrated to.
+    fn get(&self, group: GroupId) -> Option<SupergroupId>;
+
+    /// Remember that the group has been migrated to the supergroup.
+    fn insert(&self, group: GroupId, supergroup: SupergroupId);
+}
+
+/// `MigrationStore` which keeps migrations in memory.
+#[derive(Debug, Default)]
+pub struct MemoryMigrationStore {
+    migrations: RwLock<HashMap<GroupId, SupergroupId>>,
+}
+
+impl MemoryMigrationStore {
+    /// Create a new empty `MemoryMigrationStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all migrations seen so far.
+    pub fn migrations(&self) -> HashMap<GroupId, SupergroupId> {
+        self.migrations.read().unwrap().clone()
+    }
+}
+
+impl MigrationStore for MemoryMigrationStore {
+    fn get(&self, group: GroupId) -> Option<SupergroupId> {
+        self.migrations.read().unwrap().get(&group).cloned()
+    }
+
+    fn insert(&self, group: GroupId, supergroup: SupergroupId) {
+        self.migrations.write().unwrap().insert(group, supergroup);
+    }
+}
+
+/// Send the request to the supergroup if its group is known to be migrated.
+pub(crate) fn migrate_request(store: &dyn MigrationStore, request: &mut HttpRequest) {
+    if let Some(group) = request_group(request) {
+        if let Some(supergroup) = store.get(group) {
+            tracing::trace!(group = %group, supergroup = %supergroup, "sending request to migrated group");
+            request.set_chat_ref(&supergroup.to_chat_ref());
+        }
+    }
+}
+
+/// Remember the migration reported by the server and point the request to the supergroup,
+/// returns `false` if the request should not be resent.
+pub(crate) fn migrate_failed_request(
+    store: &dyn MigrationStore,
+    request: &mut HttpRequest,
+    supergroup: SupergroupId,
+) -> bool {
+    match request_group(request) {
+        Some(group) => {
+            tracing::info!(group = %group, supergroup = %supergroup, "group migrated to supergroup");
+            store.insert(group, supergroup);
+            request.set_chat_ref(&supergroup.to_chat_ref())
+        }
+        None => false,
+    }
+}
+
+/// Remember migrations announced by service messages.
+pub(crate) fn observe_update(store: &dyn MigrationStore, update: &Update) {
+    let message = match update.kind {
+        UpdateKind::Message(ref message) => message,
+        _ => return,
+    };
+
+    match (&message.kind, &message.chat) {
+        (MessageKind::MigrateToChatId { data }, MessageChat::Group(group)) => {
+            store.insert(group.id, SupergroupId::new(*data))
+        }
+        (MessageKind::MigrateFromChatId { data }, MessageChat::Supergroup(supergroup)) => {
+            store.insert(GroupId::new(*data), supergroup.id)
+        }
+        _ => (),
+    }
+}
+
+/// Returns the group targeted by the request, supergroups and channels have ids
+/// starting with `-100` and are never migrated.
+fn request_group(request: &HttpRequest) -> Option<GroupId> {
+    match request.chat_ref() {
+        Some(ChatRef::Id(id)) => {
+            let id = i64::from(id);
+            if id < 0 && id > MIN_GROUP_ID {
+                Some(GroupId::new(id))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{ChatId, Request, SendMessage};
+
+    use super::*;
+
+    fn send_message(chat: i64) -> HttpRequest {
+        SendMessage::new(ChatId::new(chat), "text")
+            .serialize()
+            .unwrap()
+    }
+
+    fn message(json: &str) -> Update {
+        let update = format!(
+            r#"{{"update_id":1,"message":{{"message_id":1,"date":0,"from":{{"id":1,"is_bot":false,"first_name":"A"}},{}}}}}"#,
+            json
+        );
+        serde_json::from_str(&update).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_request() {
+        let store = MemoryMigrationStore::new();
+        store.insert(GroupId::new(-42), SupergroupId::new(-1000000000042));
+
+        let mut request = send_message(-42);
+        migrate_request(&store, &mut request);
+        assert_eq!(
+            request.chat_ref(),
+            Some(ChatRef::Id((-1000000000042).into()))
+        );
+
+        let mut request = send_message(-43);
+        migrate_request(&store, &mut request);
+        assert_eq!(request.chat_ref(), Some(ChatRef::Id((-43).into())));
+    }
+
+    #[test]
+    fn test_migrate_failed_request() {
+        let store = MemoryMigrationStore::new();
+        let supergroup = SupergroupId::new(-1000000000042);
+
+        let mut request = send_message(-42);
+        assert!(migrate_failed_request(&store, &mut request, supergroup));
+        assert_eq!(request.chat_ref(), Some(supergroup.to_chat_ref()));
+        assert_eq!(store.get(GroupId::new(-42)), Some(supergroup));
+
+        // Supergroups, channels and private chats are never migrated.
+        for &chat in &[-1000000000042, -1001234567890, 42] {
+            let mut request = send_message(chat);
+            assert!(!migrate_failed_request(&store, &mut request, supergroup));
+        }
+        assert_eq!(store.migrations().len(), 1);
+    }
+
+    #[test]
+    fn test_observe_update() {
+        let store = MemoryMigrationStore::new();
+        observe_update(
+            &store,
+            &message(
+                r#""chat":{"id":-42,"type":"group","title":"G","all_members_are_administrators":false},"migrate_to_chat_id":-1000000000042"#,
+            ),
+        );
+        observe_update(
+            &store,
+            &message(
+                r#""chat":{"id":-1000000000043,"type":"supergroup","title":"S"},"migrate_from_chat_id":-43"#,
+            ),
+        );
+        observe_update(
+            &store,
+            &message(
+                r#""chat":{"id":-44,"type":"group","title":"G","all_members_are_administrators":false},"text":"hi""#,
+            ),
+        );
+
+        let migrations = store.migrations();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(
+            migrations[&GroupId::new(-42)],
+            SupergroupId::new(-1000000000042)
+        );
+        assert_eq!(
+            migrations[&GroupId::new(-43)],
+            SupergroupId::new(-1000000000043)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use telegram_bot_raw::Integer;
+
+use crate::atomic_file;
+use crate::errors::{Error, ErrorKind};
+
+/// Storage for the id of the last processed update of an `UpdatesStream`.
+///
+/// Implement this trait to keep the offset next to the state of your bot,
+/// so no updates are lost or processed twice after a restart.
+///
+/// The methods are called synchronously while polling the stream and from
+/// [`Acknowledger::ack`], so they should be quick.
+///
+/// [`Acknowledger::ack`]: struct.Acknowledger.html#method.ack
+pub trait OffsetStore: Debug + Send + Sync {
+    /// Returns the id of the last processed update, if any.
+    fn load(&self) -> Result<Option<Integer>, Error>;
+
+    /// Remember that all updates up to `update_id` have been processed.
+    fn store(&self, update_id: Integer) -> Result<(), Error>;
+}
+
+/// `OffsetStore` which keeps the offset in memory.
+#[derive(Debug, Default)]
+pub struct MemoryOffsetStore {
+    offset: Mutex<Option<Integer>>,
+}
+
+impl MemoryOffsetStore {
+    /// Create a new empty `MemoryOffsetStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OffsetStore for MemoryOffsetStore {
+    fn load(&self) -> Result<Option<Integer>, Error> {
+        Ok(*self.offset.lock().unwrap())
+    }
+
+    fn store(&self, update_id: Integer) -> Result<(), Error> {
+        *self.offset.lock().unwrap() = Some(update_id);
+        Ok(())
+    }
+}
+
+/// `OffsetStore` which keeps the offset in a file.
+///
+/// The file is replaced atomically on every write and synced to disk
+/// before the write returns, so a crash never loses a stored offset
+/// or leaves a partially written one behind.
+#[derive(Debug, Clone)]
+pub struct FileOffsetStore {
+    path: PathBuf,
+}
+
+impl FileOffsetStore {
+    /// Create a new `FileOffsetStore` keeping the offset in the file at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileOffsetStore { path: path.into() }
+    }
+}
+
+impl OffsetStore for FileOffsetStore {
+    fn load(&self) -> Result<Option<Integer>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(ErrorKind::from(err).into()),
+        };
+        let offset = contents.trim().parse().map_err(|_| {
+            let message = format!("invalid update offset in {}", self.path.display());
+            ErrorKind::from(io::Error::new(io::ErrorKind::InvalidData, message))
+        })?;
+        Ok(Some(offset))
+    }
+
+    fn store(&self, update_id: Integer) -> Result<(), Error> {
+        let contents = format!("{}\n", update_id);
+        atomic_file::write(&self.path, contents.as_bytes()).map_err(ErrorKind::from)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_file_offset_store() {
+        let path = env::temp_dir().join(format!("telegram-bot-offset-{}", process::id()));
+        let store = FileOffsetStore::new(&path);
+        assert_eq!(store.load().unwrap(), None);
+
+        store.store(42).unwrap();
+        store.store(43).unwrap();
+        assert_eq!(store.load().unwrap(), Some(43));
+        assert_eq!(FileOffsetStore::new(&path).load().unwrap(), Some(43));
+
+        fs::write(&path, "garbage").unwrap();
+        assert!(store.load().is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stores_differing_by_extension() {
+        let path = env::temp_dir().join(format!("telegram-bot-offsets-{}", process::id()));
+        let stores = ["a", "b"]
+            .iter()
+            .map(|extension| path.with_extension(extension))
+            .collect::<Vec<_>>();
+
+        let writers = stores
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let store = FileOffsetStore::new(path);
+                let base = index as Integer * 1000;
+                thread::spawn(move || {
+                    for offset in 0..100 {
+                        store.store(base + offset).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        for (index, path) in stores.iter().enumerate() {
+            let stored = FileOffsetStore::new(path).load().unwrap();
+            assert_eq!(stored, Some(index as Integer * 1000 + 99));
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{stream, Future, FutureExt, Stream, StreamExt};
+
+use telegram_bot_raw::{ApiUrl, BotToken, HttpRequest, HttpResponse, Update, UpdateKind};
+
+use crate::api::{Api, ApiBuilder};
+use crate::connector::{default_connector, ByteStream, Connector, UploadProgress};
+use crate::errors::Error;
+use crate::metrics::Metrics;
+
+/// Pool of `Api` instances for many bots sharing a single connector,
+/// and therefore a single connection pool.
+///
+/// # Example
+///
+/// ```rust
+/// # use futures::StreamExt;
+/// use telegram_bot::ApiPool;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pool = ApiPool::new();
+/// let support = pool.add("support", "support-token");
+/// let news = pool.add("news", "news-token");
+///
+/// let mut updates = pool.stream();
+/// # if false {
+/// while let Some((bot, update)) = updates.next().await {
+///     println!("{}: {:?}", bot, update);
+/// }
+/// # }
+/// println!("{:?}", pool.stats("support"));
+/// # }
+/// ```
+pub struct ApiPool {
+    connector: Arc<dyn Connector>,
+    bots: Mutex<HashMap<String, PooledBot>>,
+}
+
+struct PooledBot {
+    api: Api,
+    counters: Arc<Counters>,
+}
+
+/// Statistics of a bot in an [`ApiPool`].
+///
+/// [`ApiPool`]: struct.ApiPool.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BotStats {
+    /// Number of requests sent.
+    pub requests: u64,
+    /// Number of requests which failed or were rejected by the server.
+    pub errors: u64,
+    /// Number of updates received through [`ApiPool::stream`].
+    ///
+    /// [`ApiPool::stream`]: struct.ApiPool.html#method.stream
+    pub updates: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    updates: AtomicU64,
+}
+
+impl ApiPool {
+    /// Create a new `ApiPool` using the default connector.
+    pub fn new() -> Self {
+        Self::with_connector(default_connector())
+    }
+
+    /// Create a new `ApiPool` sending requests of all bots through `connector`.
+    pub fn with_connector(connector: Box<dyn Connector>) -> Self {
+        ApiPool {
+            connector: Arc::from(connector),
+            bots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a bot with the default configuration under `name`
+    /// and return its `Api` instance.
+    ///
+    /// A bot previously added under the same name is replaced.
+    pub fn add<N: Into<String>, T: Into<BotToken>>(&self, name: N, token: T) -> Api {
+        self.add_builder(name, Api::builder(token))
+    }
+
+    /// Add a bot configured by `builder` under `name` and return its `Api` instance.
+    ///
+    /// The connector of `builder` is replaced with the shared one,
+    /// layers and metrics of `builder` are kept.
+    pub fn add_builder<N: Into<String>>(&self, name: N, builder: ApiBuilder) -> Api {
+        let counters = Arc::new(Counters::default());
+        let api = builder
+            .connector(Box::new(PooledConnector {
+                inner: self.connector.clone(),
+                counters: counters.clone(),
+            }))
+            .wrap_metrics(|inner| {
+                Arc::new(PooledMetrics {
+                    inner,
+                    counters: counters.clone(),
+                })
+            })
+            .build();
+
+        let bot = PooledBot {
+            api: api.clone(),
+            counters,
+        };
+        self.bots.lock().unwrap().insert(name.into(), bot);
+        api
+    }
+
+    /// Returns the `Api` instance of the bot named `name`.
+    pub fn get(&self, name: &str) -> Option<Api> {
+        self.bots
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|bot| bot.api.clone())
+    }
+
+    /// Remove the bot named `name` from the pool.
+    ///
+    /// `Api` instances handed out before keep working.
+    pub fn remove(&self, name: &str) -> Option<Api> {
+        self.bots.lock().unwrap().remove(name).map(|bot| bot.api)
+    }
+
+    /// Returns the names of all bots in the pool.
+    pub fn names(&self) -> Vec<String> {
+        self.bots.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Returns the statistics of the bot named `name`.
+    pub fn stats(&self, name: &str) -> Option<BotStats> {
+        let bots = self.bots.lock().unwrap();
+        bots.get(name).map(|bot| bot.counters.snapshot())
+    }
+
+    /// Returns a stream of the updates of all bots currently in the pool,
+    /// every update is tagged with the name of its bot.
+    pub fn stream(&self) -> impl Stream<Item = (String, Result<Update, Error>)> + Send {
+        let bots = self.bots.lock().unwrap();
+        let streams = bots.iter().map(|(name, bot)| {
+            let name = name.clone();
+            let counters = bot.counters.clone();
+            bot.api.stream().map(move |update| {
+                if update.is_ok() {
+                    counters.updates.fetch_add(1, Ordering::Relaxed);
+                }
+                (name.clone(), update)
+            })
+        });
+        stream::select_all(streams)
+    }
+}
+
+impl Default for ApiPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ApiPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiPool")
+            .field("connector", &self.connector)
+            .field("bots", &self.names())
+            .finish()
+    }
+}
+
+impl Counters {
+    fn snapshot(&self) -> BotStats {
+        BotStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            updates: self.updates.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Metrics of a single bot in the pool, counting requests before forwarding them.
+struct PooledMetrics {
+    inner: Arc<dyn Metrics>,
+    counters: Arc<Counters>,
+}
+
+impl Metrics for PooledMetrics {
+    fn request(&self, method: &'static str, elapsed: Duration, result: Result<(), &Error>) {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.inner.request(method, elapsed, result)
+    }
+
+    fn update(&self, kind: &UpdateKind, lag: Option<Duration>) {
+        self.inner.update(kind, lag)
+    }
+
+    fn poll(&self, elapsed: Duration, result: Result<usize, &Error>) {
+        self.inner.poll(elapsed, result)
+    }
+}
+
+/// Connector of a single bot in the pool, forwarding to the shared connector.
+///
+/// Requests are counted by `PooledMetrics` from their decoded results,
+/// file downloads don't go through `Api::send` and are counted here.
+struct PooledConnector {
+    inner: Arc<dyn Connector>,
+    counters: Arc<Counters>,
+}
+
+impl fmt::Debug for PooledConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledConnector")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl Connector for PooledConnector {
+    fn request(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.inner.request(api_url, token, req)
+    }
+
+    fn upload(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+        progress: UploadProgress,
+    ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+        self.inner.upload(api_url, token, req, progress)
+    }
+
+    fn download(
+        &self,
+        api_url: &ApiUrl,
+        token: &BotToken,
+        req: HttpRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ByteStream, Error>> + Send>> {
+        let counters = self.counters.clone();
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        let response = self.inner.download(api_url, token, req);
+
+        async move {
+            let result = response.await;
+            if result.is_err() {
+                counters.errors.fetch_add(1, Ordering::Relaxed);
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::GetMe;
+
+    use super::*;
+    use crate::test_support::replay_connector;
+
+    #[tokio::test]
+    async fn test_pool_stats() {
+        let connector = replay_connector(&[(
+            "getMe",
+            r#"{"ok":false,"error_code":401,"description":"Unauthorized"}"#,
+        )]);
+        let pool = ApiPool::with_connector(Box::new(connector));
+        let first = pool.add("first", "first-token");
+        pool.add("second", "second-token");
+
+        assert!(first.send(GetMe).await.is_err());
+        assert_eq!(
+            pool.stats("first"),
+            Some(BotStats {
+                requests: 1,
+                errors: 1,
+                updates: 0,
+            })
+        );
+        assert_eq!(pool.stats("second"), Some(BotStats::default()));
+
+        let mut names = pool.names();
+        names.sort();
+        assert_eq!(names, ["first", "second"]);
+        assert!(pool.remove("second").is_some());
+        assert!(pool.get("second").is_none());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::errors::Error;
+
+const DEFAULT_MAX_ATTEMPTS: usize = 3;
+const DEFAULT_MAX_WAIT_SECONDS: u64 = 60;
+
+/// Policy for resending requests rejected by the Telegram flood control.
+///
+/// When the server answers with `retry_after` in the response parameters,
+/// `Api` waits for the requested duration and sends the request again.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telegram_bot::{Api, RetryPolicy};
+///
+/// let mut policy = RetryPolicy::new();
+/// policy
+///     .max_attempts(5)
+///     .max_wait(Duration::from_secs(30))
+///     .methods(&["sendMessage", "editMessageText"]);
+///
+/// let api = Api::builder("token").retry_policy(policy).build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    max_wait: Duration,
+    methods: Option<HashSet<String>>,
+}
+
+impl RetryPolicy {
+    /// Create a new `RetryPolicy` which retries every method.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            max_wait: Duration::from_secs(DEFAULT_MAX_WAIT_SECONDS),
+            methods: None,
+        }
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    ///
+    /// Defaults to 3.
+    pub fn max_attempts(&mut self, max_attempts: usize) -> &mut Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the maximum total time spent waiting before retries of a single request.
+    /// Request is not retried if the server asks to wait longer than that.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn max_wait(&mut self, max_wait: Duration) -> &mut Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Restrict retries to the listed Bot API methods, e.g. `"sendMessage"`.
+    ///
+    /// All methods are retried by default.
+    pub fn methods(&mut self, methods: &[&str]) -> &mut Self {
+        self.methods = Some(methods.iter().map(|method| method.to_string()).collect());
+        self
+    }
+
+    /// Returns delay before the next attempt or `None` if the request
+    /// should not be retried.
+    pub(crate) fn retry_delay(
+        &self,
+        method: &str,
+        attempt: usize,
+        waited: Duration,
+        error: &Error,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if let Some(ref methods) = self.methods {
+            if !methods.contains(method) {
+                return None;
+            }
+        }
+        let delay = error.retry_after()?;
+        if waited + delay > self.max_wait {
+            return None;
+        }
+        Some(delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telegram_bot_raw::{HttpResponse, JsonTrueToUnitResponse, ResponseType};
+
+    use super::*;
+    use crate::errors::ErrorKind;
+
+    fn flood_error(retry_after: u64) -> Error {
+        let body = format!(
+            r#"{{"ok":false,"error_code":429,"description":"Too Many Requests","parameters":{{"retry_after":{}}}}}"#,
+            retry_after
+        );
+        let response = HttpResponse {
+            body: Some(body.into_bytes()),
+        };
+        JsonTrueToUnitResponse::deserialize(response)
+            .map_err(ErrorKind::from)
+            .unwrap_err()
+            .into()
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let mut policy = RetryPolicy::new();
+        policy.max_attempts(3).max_wait(Duration::from_secs(10));
+
+        let error = flood_error(4);
+        let no_wait = Duration::from_secs(0);
+        assert_eq!(
+            policy.retry_delay("sendMessage", 1, no_wait, &error),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(policy.retry_delay("sendMessage", 3, no_wait, &error), None);
+        assert_eq!(
+            policy.retry_delay("sendMessage", 2, Duration::from_secs(8), &error),
+            None
+        );
+
+        policy.methods(&["editMessageText"]);
+        assert_eq!(policy.retry_delay("sendMessage", 1, no_wait, &error), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Abstraction over the async runtime used to spawn tasks, wait and access files.
+//!
+//! [`TokioRuntime`] is available with the default `tokio-runtime` feature. The `thread-runtime`
+//! feature adds [`ThreadRuntime`], which doesn't use tokio and becomes the default runtime.
+//! The default connector and [`Webhook`] are built on hyper and still
+//! need a tokio reactor, use a [`Connector`] of your own to send requests without tokio.
+//!
+//! [`TokioRuntime`]: struct.TokioRuntime.html
+//! [`ThreadRuntime`]: struct.ThreadRuntime.html
+//! [`Webhook`]: ../struct.Webhook.html
+//! [`Connector`]: ../connector/trait.Connector.html
+
+#[cfg(feature = "thread-runtime")]
+mod thread;
+#[cfg(feature = "tokio-runtime")]
+mod tokio;
+
+#[cfg(feature = "thread-runtime")]
+pub use self::thread::ThreadRuntime;
+#[cfg(feature = "tokio-runtime")]
+pub use self::tokio::TokioRuntime;
+
+#[cfg(not(any(feature = "tokio-runtime", feature = "thread-runtime")))]
+compile_error!("either the `tokio-runtime` or the `thread-runtime` feature must be enabled");
+
+use std::fmt::Debug;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::connector::ByteStream;
+use crate::errors::{Error, ErrorKind};
+use futures::future::{self, Either};
+use futures::{stream, Future, Stream, StreamExt};
+
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stream of file contents in chunks.
+pub type FileStream = Pin<Box<dyn Stream<Item = Result<Bytes, io::Error>> + Send>>;
+
+/// Runtime used by `Api` for background work.
+pub trait Runtime: Debug + Send + Sync {
+    /// Run `future` in the background.
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>);
+
+    /// Returns a future which completes after `duration`.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    /// Read the file at `path` in chunks.
+    fn read_file(&self, path: PathBuf) -> FileStream;
+
+    /// Returns the size of the file at `path`.
+    fn file_len(&self, path: PathBuf) -> Pin<Box<dyn Future<Output = io::Result<u64>> + Send>>;
+
+    /// Write `contents` to a new file at `path`, returns the number of bytes written.
+    ///
+    /// The file is removed if writing fails or `contents` yields an error.
+    fn write_file(
+        &self,
+        path: PathBuf,
+        contents: ByteStream,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>>;
+}
+
+/// Returns the runtime selected by the enabled features.
+#[cfg(feature = "thread-runtime")]
+pub fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(ThreadRuntime)
+}
+
+/// Returns the runtime selected by the enabled features.
+#[cfg(all(feature = "tokio-runtime", not(feature = "thread-runtime")))]
+pub fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(TokioRuntime)
+}
+
+/// Wait for `future` for at most `duration`, returns `None` if it didn't complete in time.
+pub(crate) async fn timeout<F: Future>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output> {
+    let sleep = runtime.sleep(duration);
+    futures::pin_mut!(future);
+    match future::select(future, sleep).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(((), _)) => None,
+    }
+}
+
+/// Fail `body` if the next chunk doesn't arrive within `duration`.
+pub(crate) fn timeout_stream(
+    runtime: Arc<dyn Runtime>,
+    duration: Duration,
+    body: ByteStream,
+) -> ByteStream {
+    stream::unfold(Some(body), move |body| {
+        let runtime = runtime.clone();
+        async move {
+            let mut body = body?;
+            match timeout(&*runtime, duration, body.next()).await {
+                Some(Some(chunk)) => Some((chunk, Some(body))),
+                Some(None) => None,
+                None => Some((Err(ErrorKind::Timeout(duration).into()), None)),
+            }
+        }
+    })
+    .boxed()
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::channel::{mpsc, oneshot};
+use futures::{executor, Future, FutureExt, SinkExt, StreamExt};
+use lazy_static::lazy_static;
+
+use super::{FileStream, Runtime, FILE_CHUNK_SIZE};
+use crate::connector::ByteStream;
+use crate::errors::{Error, ErrorKind};
+
+type Wakeup = (Instant, oneshot::Sender<()>);
+
+lazy_static! {
+    static ref TIMER: Mutex<std_mpsc::Sender<Wakeup>> = Mutex::new(start_timer());
+}
+
+/// Runtime which does blocking work on dedicated threads instead of using tokio.
+///
+/// All sleeps are served by a single shared timer thread. Every spawned task and
+/// file operation gets a thread of its own, which is fine for the occasional
+/// background request but not for heavy workloads. Spawned tasks run outside of any
+/// tokio runtime, so they can't use the default hyper-based connector.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadRuntime;
+
+impl Runtime for ThreadRuntime {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        thread::spawn(move || executor::block_on(future));
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let (sender, receiver) = oneshot::channel();
+        let _ = TIMER
+            .lock()
+            .unwrap()
+            .send((Instant::now() + duration, sender));
+        receiver.map(|_| ()).boxed()
+    }
+
+    fn read_file(&self, path: PathBuf) -> FileStream {
+        let (mut sender, receiver) = mpsc::channel(1);
+        thread::spawn(move || {
+            let mut file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) => {
+                    let _ = executor::block_on(sender.send(Err(err)));
+                    return;
+                }
+            };
+            loop {
+                let mut buffer = vec![0; FILE_CHUNK_SIZE];
+                let chunk = match file.read(&mut buffer) {
+                    Ok(0) => return,
+                    Ok(len) => {
+                        buffer.truncate(len);
+                        Ok(buffer.into())
+                    }
+                    Err(err) => Err(err),
+                };
+                let failed = chunk.is_err();
+                if executor::block_on(sender.send(chunk)).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        receiver.boxed()
+    }
+
+    fn file_len(&self, path: PathBuf) -> Pin<Box<dyn Future<Output = io::Result<u64>> + Send>> {
+        let (sender, receiver) = oneshot::channel();
+        thread::spawn(move || {
+            let _ = sender.send(fs::metadata(path).map(|metadata| metadata.len()));
+        });
+        receiver
+            .map(|result| result.unwrap_or_else(|_| Err(thread_gone())))
+            .boxed()
+    }
+
+    fn write_file(
+        &self,
+        path: PathBuf,
+        contents: ByteStream,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>> {
+        let (chunks, receiver) = mpsc::channel(1);
+        let (sender, written) = oneshot::channel();
+        thread::spawn(move || {
+            let result = write_chunks(&path, executor::block_on_stream(receiver));
+            if result.is_err() {
+                let _ = fs::remove_file(&path);
+            }
+            let _ = sender.send(result);
+        });
+
+        async move {
+            // Stops early when the writer thread fails and drops the receiver.
+            let _ = contents.map(Ok).forward(chunks).await;
+            written
+                .await
+                .unwrap_or_else(|_| Err(ErrorKind::from(thread_gone()).into()))
+        }
+        .boxed()
+    }
+}
+
+fn write_chunks<I>(path: &Path, contents: I) -> Result<u64, Error>
+where
+    I: Iterator<Item = Result<Bytes, Error>>,
+{
+    let mut file = File::create(path).map_err(ErrorKind::from)?;
+    let mut size = 0;
+    for chunk in contents {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        file.write_all(&chunk).map_err(ErrorKind::from)?;
+    }
+    file.flush().map_err(ErrorKind::from)?;
+    Ok(size)
+}
+
+// `io::Error::other` needs a newer compiler than the rest of the crate.
+#[allow(clippy::io_other_error)]
+fn thread_gone() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "runtime thread terminated unexpectedly",
+    )
+}
+
+fn start_timer() -> std_mpsc::Sender<Wakeup> {
+    let (sender, receiver) = std_mpsc::channel();
+    thread::Builder::new()
+        .name("telegram-bot-timer".into())
+        .spawn(move || run_timer(receiver))
+        .expect("unable to spawn the timer thread");
+    sender
+}
+
+fn run_timer(receiver: std_mpsc::Receiver<Wakeup>) {
+    let mut sleeps: BTreeMap<(Instant, u64), oneshot::Sender<()>> = BTreeMap::new();
+    let mut next_id = 0u64;
+    loop {
+        let now = Instant::now();
+        let pending = sleeps.split_off(&(now, u64::MAX));
+        for (_, sender) in mem::replace(&mut sleeps, pending) {
+            let _ = sender.send(());
+        }
+
+        let received = match sleeps.keys().next() {
+            Some(&(deadline, _)) => receiver.recv_timeout(deadline - now),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok((deadline, sender)) => {
+                next_id += 1;
+                sleeps.insert((deadline, next_id), sender);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use std::time::Instant;
+
+    use bytes::Bytes;
+    use futures::future::{self, Either};
+    use futures::{stream, TryStreamExt};
+
+    use super::*;
+    use crate::runtime::timeout;
+
+    #[test]
+    fn test_thread_runtime() {
+        let runtime = ThreadRuntime;
+        let path = env::temp_dir().join(format!("telegram-bot-runtime-{}.txt", process::id()));
+        std::fs::write(&path, vec![b'x'; FILE_CHUNK_SIZE + 1]).unwrap();
+
+        let chunks: Vec<Bytes> =
+            executor::block_on(runtime.read_file(path.clone()).try_collect()).unwrap();
+        let sizes = chunks.iter().map(Bytes::len).collect::<Vec<_>>();
+        assert_eq!(sizes, [FILE_CHUNK_SIZE, 1]);
+        let len = executor::block_on(runtime.file_len(path.clone())).unwrap();
+        assert_eq!(len, FILE_CHUNK_SIZE as u64 + 1);
+
+        let contents = stream::iter(chunks.into_iter().map(Ok)).boxed();
+        let written = executor::block_on(runtime.write_file(path.clone(), contents)).unwrap();
+        assert_eq!(written, FILE_CHUNK_SIZE as u64 + 1);
+        let failed: ByteStream =
+            stream::once(async { Err(ErrorKind::FileUnavailable.into()) }).boxed();
+        assert!(executor::block_on(runtime.write_file(path.clone(), failed)).is_err());
+        assert!(!path.exists());
+        assert!(executor::block_on(runtime.file_len(path.clone())).is_err());
+        assert!(executor::block_on(runtime.read_file(path).next())
+            .unwrap()
+            .is_err());
+
+        let started = Instant::now();
+        let never = futures::future::pending::<()>();
+        let result = executor::block_on(timeout(&runtime, Duration::from_millis(10), never));
+        assert_eq!(result, None);
+        assert!(started.elapsed() >= Duration::from_millis(10));
+
+        let started = Instant::now();
+        let long = runtime.sleep(Duration::from_millis(50));
+        let short = runtime.sleep(Duration::from_millis(20));
+        match executor::block_on(future::select(long, short)) {
+            Either::Right(((), long)) => executor::block_on(long),
+            Either::Left(_) => panic!("longer sleep completed first"),
+        }
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::{stream, Future, FutureExt, StreamExt};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::{FileStream, Runtime, FILE_CHUNK_SIZE};
+use crate::connector::ByteStream;
+use crate::errors::{Error, ErrorKind};
+
+/// Runtime backed by tokio, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        tokio::spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        tokio::time::delay_for(duration).boxed()
+    }
+
+    fn read_file(&self, path: PathBuf) -> FileStream {
+        let file = async move { File::open(path).await };
+        stream::once(file)
+            .map(|file| match file {
+                Ok(file) => chunks(file).boxed(),
+                Err(err) => stream::once(async { Err(err) }).boxed(),
+            })
+            .flatten()
+            .boxed()
+    }
+
+    fn file_len(&self, path: PathBuf) -> Pin<Box<dyn Future<Output = io::Result<u64>> + Send>> {
+        async move { Ok(fs::metadata(path).await?.len()) }.boxed()
+    }
+
+    fn write_file(
+        &self,
+        path: PathBuf,
+        contents: ByteStream,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>> {
+        async move {
+            let result = write_chunks(&path, contents).await;
+            if result.is_err() {
+                let _ = fs::remove_file(&path).await;
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+async fn write_chunks(path: &Path, mut contents: ByteStream) -> Result<u64, Error> {
+    let mut file = File::create(path).await.map_err(ErrorKind::from)?;
+    let mut size = 0;
+    while let Some(chunk) = contents.next().await {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        file.write_all(&chunk).await.map_err(ErrorKind::from)?;
+    }
+    file.flush().await.map_err(ErrorKind::from)?;
+    Ok(size)
+}
+
+fn chunks(file: File) -> impl futures::Stream<Item = Result<Bytes, io::Error>> + Send {
+    stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0; FILE_CHUNK_SIZE];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(len) => {
+                buffer.truncate(len);
+                Some((Ok(buffer.into()), Some(file)))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
@@ -1,24 +1,70 @@
-use std::cmp::max;
-use std::collections::VecDeque;
+use std::cmp::{max, min};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeSet, VecDeque};
 use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::Context;
 use std::task::Poll;
-use std::time::Duration;
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
 use futures::Stream;
 
-use telegram_bot_raw::{AllowedUpdate, GetUpdates, Integer, Update};
+use telegram_bot_raw::{
+    AllowedUpdate, CallbackQuery, ChannelPost, GetUpdates, InlineQuery, Integer, Message, Update,
+    UpdateKind,
+};
 
 use crate::api::Api;
 use crate::errors::Error;
+use crate::metrics::update_lag;
+use crate::offset::OffsetStore;
+use crate::view::UpdateView;
 
 const TELEGRAM_LONG_POLL_TIMEOUT_SECONDS: u64 = 5;
 const TELEGRAM_LONG_POLL_LIMIT_MESSAGES: Integer = 100;
 const TELEGRAM_LONG_POLL_ERROR_DELAY_MILLISECONDS: u64 = 500;
+const TELEGRAM_LONG_POLL_MAX_ERROR_DELAY_SECONDS: u64 = 60;
+const TELEGRAM_LONG_POLL_CIRCUIT_BREAKER_FAILURES: u32 = 10;
+const TELEGRAM_LONG_POLL_WATCHDOG_SECONDS: u64 = 10;
 
 /// This type represents stream of Telegram API updates and uses
 /// long polling method under the hood.
+///
+/// # Acknowledgments
+///
+/// By default an update is confirmed to Telegram as soon as the next batch of updates
+/// is requested. In [`ack_mode`](#method.ack_mode) updates are only confirmed once they
+/// are acknowledged, together with an [`OffsetStore`] this gives at-least-once processing
+/// across restarts.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use futures::StreamExt;
+/// # use telegram_bot::{Api, FileOffsetStore};
+/// #
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let telegram_token = "token";
+/// # let api = Api::new(telegram_token);
+/// # if false {
+/// let mut stream = api.stream();
+/// stream
+///     .offset_store(Arc::new(FileOffsetStore::new("offset")))
+///     .ack_mode(true);
+///
+/// while let Some(update) = stream.next().await {
+///     let update = update.unwrap();
+///     println!("{:?}", update);
+///     stream.ack(update.id).unwrap();
+/// }
+/// # }
+/// # }
+/// ```
+///
+/// [`OffsetStore`]: trait.OffsetStore.html
 #[must_use = "streams do nothing unless polled"]
 pub struct UpdatesStream {
     api: Api,
@@ -30,7 +76,56 @@ pub struct UpdatesStream {
     allowed_updates: Vec<AllowedUpdate>,
     limit: Integer,
     error_delay: Duration,
+    max_error_delay: Duration,
+    circuit_breaker: u32,
+    watchdog: Duration,
+    delay: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    failures: u32,
+    health: Arc<Mutex<Health>>,
     next_poll_id: usize,
+    poll_started: Instant,
+    offsets: Arc<Mutex<Offsets>>,
+    ack_mode: bool,
+    offset_loaded: bool,
+    stored_update: Integer,
+}
+
+/// Health of an `UpdatesStream`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// The last long polling request succeeded.
+    Healthy,
+    /// The last `failures` requests failed, the stream is backing off.
+    Degraded { failures: u32 },
+    /// The last request got no response in time, the connection may be dead.
+    Stalled,
+    /// Too many requests failed in a row, requests are only retried after the maximal delay.
+    CircuitOpen,
+}
+
+/// Handle reading the health of an `UpdatesStream`, e.g. from a health check endpoint.
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    health: Arc<Mutex<Health>>,
+}
+
+/// Handle acknowledging updates of an `UpdatesStream` in [ack mode](struct.UpdatesStream.html#method.ack_mode),
+/// e.g. from the tasks processing them.
+#[derive(Debug, Clone)]
+pub struct Acknowledger {
+    offsets: Arc<Mutex<Offsets>>,
+}
+
+#[derive(Debug, Default)]
+struct Offsets {
+    /// All updates up to this id are acknowledged.
+    acked: Integer,
+    /// Id of the last update received in ack mode.
+    received: Integer,
+    /// Received updates which are not acknowledged yet.
+    unacked: BTreeSet<Integer>,
+    waker: Option<Waker>,
+    store: Option<Arc<dyn OffsetStore>>,
 }
 
 impl Stream for UpdatesStream {
@@ -45,87 +140,83 @@ impl Stream for UpdatesStream {
 
         tracing::trace!("start stream polling");
 
-        if let Some(value) = ref_mut.buffer.pop_front() {
-            tracing::trace!(update = ?value, "returning buffered update");
-            return Poll::Ready(Some(Ok(value)));
-        }
-        tracing::trace!("processing request");
-
-        let result = match ref_mut.current_request {
-            None => {
-                tracing::trace!("there is no current request");
-                Ok(false)
+        loop {
+            if let Some(value) = ref_mut.buffer.pop_front() {
+                tracing::trace!(update = ?value, "returning buffered update");
+                return Poll::Ready(Some(Ok(value)));
             }
-            Some(ref mut current_request) => {
-                let cc = current_request.as_mut();
-                let polled_update = cc.poll(cx);
-                match polled_update {
+
+            if let Some(ref mut delay) = ref_mut.delay {
+                match delay.as_mut().poll(cx) {
+                    Poll::Ready(()) => ref_mut.delay = None,
                     Poll::Pending => {
-                        tracing::trace!("request is pending");
+                        tracing::trace!("backing off");
                         return Poll::Pending;
                     }
-                    Poll::Ready(Ok(None)) => {
-                        tracing::trace!("request timed out");
-                        Ok(false)
-                    }
-                    Poll::Ready(Ok(Some(ref updates))) if updates.is_empty() => {
-                        tracing::trace!("request resolved to empty update list");
-                        Ok(false)
+                }
+            }
+
+            let current_request = match ref_mut.current_request {
+                Some(ref mut current_request) => current_request,
+                None => {
+                    tracing::trace!("there is no current request");
+                    match ref_mut.poll_offset(cx) {
+                        Poll::Ready(Ok(())) => ref_mut.start_request(),
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                        Poll::Pending => return Poll::Pending,
                     }
-                    Poll::Ready(Ok(Some(updates))) => {
-                        for update in updates {
-                            tracing::trace!(update = ?update, "processing update");
-                            ref_mut.last_update = max(update.id, ref_mut.last_update);
-                            tracing::trace!(last_update = ref_mut.last_update);
-                            ref_mut.buffer.push_back(update)
-                        }
+                    continue;
+                }
+            };
 
-                        Ok(true)
+            let result = match current_request.as_mut().poll(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => {
+                    tracing::trace!("request is pending");
+                    return Poll::Pending;
+                }
+            };
+            ref_mut.current_request = None;
+
+            let received = match result {
+                Ok(ref updates) => Ok(updates.as_ref().map_or(0, Vec::len)),
+                Err(ref err) => Err(err),
+            };
+            let elapsed = ref_mut.poll_started.elapsed();
+            ref_mut.api.metrics().poll(elapsed, received);
+
+            match result {
+                Ok(Some(updates)) => {
+                    ref_mut.succeeded();
+                    if ref_mut.ack_mode {
+                        let mut offsets = ref_mut.offsets.lock().unwrap();
+                        for update in &updates {
+                            offsets.unacked.insert(update.id);
+                            offsets.received = max(update.id, offsets.received);
+                        }
                     }
-                    Poll::Ready(Err(err)) => {
-                        tracing::error!(error = %err, "request error");
-                        Err(err)
+                    for update in updates {
+                        tracing::trace!(update = ?update, "processing update");
+                        ref_mut.api.observe_update(&update);
+                        ref_mut
+                            .api
+                            .metrics()
+                            .update(&update.kind, update_lag(&update));
+                        ref_mut.last_update = max(update.id, ref_mut.last_update);
+                        tracing::trace!(last_update = ref_mut.last_update);
+                        ref_mut.buffer.push_back(update)
                     }
                 }
-            }
-        };
-
-        match result {
-            Err(err) => {
-                let timeout = ref_mut.timeout + Duration::from_secs(1);
-                let mut get_updates = GetUpdates::new();
-                get_updates
-                    .offset(ref_mut.last_update + 1)
-                    .timeout(ref_mut.error_delay.as_secs() as Integer)
-                    .limit(ref_mut.limit)
-                    .allowed_updates(&ref_mut.allowed_updates);
-                tracing::trace!(request = ?get_updates, timeout=?timeout, "preparing new request");
-
-                let request = ref_mut.api.send_timeout(get_updates, timeout);
-                ref_mut.current_request = Some(Box::pin(request));
-                return Poll::Ready(Some(Err(err)));
-            }
-            Ok(false) => {
-                let timeout = ref_mut.timeout + Duration::from_secs(1);
-                let mut get_updates = GetUpdates::new();
-                get_updates
-                    .offset(ref_mut.last_update + 1)
-                    .timeout(ref_mut.error_delay.as_secs() as Integer)
-                    .limit(ref_mut.limit)
-                    .allowed_updates(&ref_mut.allowed_updates);
-                tracing::trace!(request = ?get_updates, timeout=?timeout, "preparing new request");
-
-                let request = ref_mut.api.send_timeout(get_updates, timeout);
-                ref_mut.current_request = Some(Box::pin(request));
-
-                tracing::trace!("executing recursive call");
-                Pin::new(ref_mut).poll_next(cx)
-            }
-            Ok(true) => {
-                tracing::trace!("dropping request");
-                ref_mut.current_request = None;
-                tracing::trace!("executing recursive call");
-                Pin::new(ref_mut).poll_next(cx)
+                Ok(None) => {
+                    tracing::warn!(elapsed = ?elapsed, "long polling request stalled");
+                    ref_mut.failed(Health::Stalled);
+                }
+                Err(err) => {
+                    tracing::error!(error = %err, "request error");
+                    let failures = ref_mut.failures + 1;
+                    ref_mut.failed(Health::Degraded { failures });
+                    return Poll::Ready(Some(Err(err)));
+                }
             }
         }
     }
@@ -143,14 +234,25 @@ impl UpdatesStream {
             allowed_updates: Vec::new(),
             limit: TELEGRAM_LONG_POLL_LIMIT_MESSAGES,
             error_delay: Duration::from_millis(TELEGRAM_LONG_POLL_ERROR_DELAY_MILLISECONDS),
+            max_error_delay: Duration::from_secs(TELEGRAM_LONG_POLL_MAX_ERROR_DELAY_SECONDS),
+            circuit_breaker: TELEGRAM_LONG_POLL_CIRCUIT_BREAKER_FAILURES,
+            watchdog: Duration::from_secs(TELEGRAM_LONG_POLL_WATCHDOG_SECONDS),
+            delay: None,
+            failures: 0,
+            health: Arc::new(Mutex::new(Health::Healthy)),
             next_poll_id: 0,
+            poll_started: Instant::now(),
+            offsets: Arc::new(Mutex::new(Offsets::default())),
+            ack_mode: false,
+            offset_loaded: false,
+            stored_update: 0,
         }
     }
 
     /// Set timeout for long polling requests, this corresponds with `timeout` field
     /// in [getUpdates](https://core.telegram.org/bots/api#getupdates) method,
-    /// also this stream sets an additional request timeout for `timeout + 1 second`
-    /// in case of invalid Telegram API server behaviour.
+    /// also this stream sets an additional request timeout for `timeout` plus the
+    /// [`watchdog`](#method.watchdog) grace period in case of invalid Telegram API server behaviour.
     ///
     /// Default timeout is 5 seconds.
     pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
@@ -185,9 +287,385 @@ impl UpdatesStream {
     /// Set a delay between erroneous request and next request.
     /// This delay prevents busy looping in some cases.
     ///
+    /// The delay is doubled with every further failed request, up to
+    /// [`max_error_delay`](#method.max_error_delay), and randomized by up to a half
+    /// so many bots don't retry in lockstep.
+    ///
     /// Default delay is 500 ms.
     pub fn error_delay(&mut self, delay: Duration) -> &mut Self {
         self.error_delay = delay;
         self
     }
+
+    /// Set the maximal delay between erroneous request and next request.
+    ///
+    /// Default delay is 60 seconds.
+    pub fn max_error_delay(&mut self, delay: Duration) -> &mut Self {
+        self.max_error_delay = delay;
+        self
+    }
+
+    /// Open the circuit after `failures` requests failed in a row: until a request
+    /// succeeds again, requests are only retried after the maximal delay.
+    ///
+    /// Defaults to 10 failures.
+    pub fn circuit_breaker(&mut self, failures: u32) -> &mut Self {
+        self.circuit_breaker = failures;
+        self
+    }
+
+    /// Consider a long polling request stalled if it got no response `grace` after
+    /// the long polling timeout ran out. Stalled requests are abandoned and retried
+    /// like failed ones.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn watchdog(&mut self, grace: Duration) -> &mut Self {
+        self.watchdog = grace;
+        self
+    }
+
+    /// Returns the current health of the stream.
+    pub fn health(&self) -> Health {
+        *self.health.lock().unwrap()
+    }
+
+    /// Returns a handle reading the health of this stream.
+    pub fn health_monitor(&self) -> HealthMonitor {
+        HealthMonitor {
+            health: self.health.clone(),
+        }
+    }
+
+    /// Persist the offset of the stream in `store` and continue after the update
+    /// stored there, instead of after the last update confirmed to Telegram.
+    ///
+    /// No store is used by default, so a stream never writes files unless asked to.
+    /// Use a [`FileOffsetStore`](struct.FileOffsetStore.html) to keep the offset
+    /// across restarts.
+    pub fn offset_store(&mut self, store: Arc<dyn OffsetStore>) -> &mut Self {
+        self.offsets.lock().unwrap().store = Some(store);
+        self.offset_loaded = false;
+        self
+    }
+
+    /// Only confirm updates to Telegram once they are acknowledged with [`ack`](#method.ack)
+    /// or an [`Acknowledger`](struct.Acknowledger.html).
+    ///
+    /// New updates are requested once all received updates are acknowledged,
+    /// unacknowledged updates are received again after a restart.
+    ///
+    /// Defaults to `false`.
+    pub fn ack_mode(&mut self, ack_mode: bool) -> &mut Self {
+        self.ack_mode = ack_mode;
+        self
+    }
+
+    /// Acknowledge that the update `update_id` has been processed.
+    ///
+    /// See [`Acknowledger::ack`](struct.Acknowledger.html#method.ack).
+    pub fn ack(&self, update_id: Integer) -> Result<(), Error> {
+        self.acknowledger().ack(update_id)
+    }
+
+    /// Returns a handle acknowledging updates of this stream.
+    pub fn acknowledger(&self) -> Acknowledger {
+        Acknowledger {
+            offsets: self.offsets.clone(),
+        }
+    }
+
+    /// Returns a stream of new incoming messages.
+    ///
+    /// Unless [allowed updates](#method.allowed_updates) were set, only messages are requested.
+    /// In [ack mode](#method.ack_mode) an update is acknowledged when the view skips it
+    /// or when the item after it is requested, so process items before polling again.
+    ///
+    /// ```rust
+    /// # use futures::StreamExt;
+    /// # use telegram_bot::{Api, Filter};
+    /// #
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let api = Api::new("token");
+    /// # if false {
+    /// let mut commands = api.stream().messages().matching(Filter::command("start"));
+    /// while let Some(message) = commands.next().await {
+    ///     println!("{:?}", message.unwrap());
+    /// }
+    /// # }
+    /// # }
+    /// ```
+    pub fn messages(self) -> UpdateView<Message> {
+        self.view(AllowedUpdate::Message, |kind| match kind {
+            UpdateKind::Message(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of edited messages.
+    ///
+    /// Unless [allowed updates](#method.allowed_updates) were set, only edited messages are requested.
+    /// In [ack mode](#method.ack_mode) an update is acknowledged when the view skips it
+    /// or when the item after it is requested, so process items before polling again.
+    pub fn edited_messages(self) -> UpdateView<Message> {
+        self.view(AllowedUpdate::EditedMessage, |kind| match kind {
+            UpdateKind::EditedMessage(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of new incoming channel posts.
+    ///
+    /// Unless [allowed updates](#method.allowed_updates) were set, only channel posts are requested.
+    /// In [ack mode](#method.ack_mode) an update is acknowledged when the view skips it
+    /// or when the item after it is requested, so process items before polling again.
+    pub fn channel_posts(self) -> UpdateView<ChannelPost> {
+        self.view(AllowedUpdate::ChannelPost, |kind| match kind {
+            UpdateKind::ChannelPost(post) => Some(post),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of callback queries.
+    ///
+    /// Unless [allowed updates](#method.allowed_updates) were set, only callback queries are requested.
+    /// In [ack mode](#method.ack_mode) an update is acknowledged when the view skips it
+    /// or when the item after it is requested, so process items before polling again.
+    pub fn callback_queries(self) -> UpdateView<CallbackQuery> {
+        self.view(AllowedUpdate::CallbackQuery, |kind| match kind {
+            UpdateKind::CallbackQuery(query) => Some(query),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of inline queries.
+    ///
+    /// Unless [allowed updates](#method.allowed_updates) were set, only inline queries are requested.
+    /// In [ack mode](#method.ack_mode) an update is acknowledged when the view skips it
+    /// or when the item after it is requested, so process items before polling again.
+    pub fn inline_queries(self) -> UpdateView<InlineQuery> {
+        self.view(AllowedUpdate::InlineQuery, |kind| match kind {
+            UpdateKind::InlineQuery(query) => Some(query),
+            _ => None,
+        })
+    }
+
+    fn view<T>(
+        mut self,
+        allowed_update: AllowedUpdate,
+        extract: fn(UpdateKind) -> Option<T>,
+    ) -> UpdateView<T> {
+        if self.allowed_updates.is_empty() {
+            self.allowed_updates = vec![allowed_update];
+        }
+        UpdateView::new(self, extract)
+    }
+
+    pub(crate) fn is_ack_mode(&self) -> bool {
+        self.ack_mode
+    }
+
+    /// Load the persisted offset and, in ack mode, wait until all received
+    /// updates are acknowledged before the next request.
+    fn poll_offset(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let mut offsets = self.offsets.lock().unwrap();
+        let store = offsets.store.clone();
+
+        if let (Some(store), false) = (&store, self.offset_loaded) {
+            if let Some(update_id) = store.load()? {
+                tracing::debug!(update_id = update_id, "continuing after persisted offset");
+                self.last_update = update_id;
+                self.stored_update = update_id;
+                offsets.acked = update_id;
+            }
+        }
+        self.offset_loaded = true;
+
+        if self.ack_mode {
+            if offsets.acked < self.last_update {
+                tracing::trace!(acked = offsets.acked, "waiting for acknowledgments");
+                offsets.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        } else if let (Some(store), true) = (&store, self.last_update > self.stored_update) {
+            store.store(self.last_update)?;
+            self.stored_update = self.last_update;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_request(&mut self) {
+        let timeout = self.timeout + self.watchdog;
+        let mut get_updates = GetUpdates::new();
+        get_updates
+            .offset(self.last_update + 1)
+            .timeout(self.timeout.as_secs() as Integer)
+            .limit(self.limit)
+            .allowed_updates(&self.allowed_updates);
+        tracing::trace!(request = ?get_updates, timeout = ?timeout, "preparing new request");
+
+        let request = self.api.send_timeout(get_updates, timeout);
+        self.current_request = Some(Box::pin(request));
+        self.poll_started = Instant::now();
+    }
+
+    fn succeeded(&mut self) {
+        if self.failures > 0 {
+            tracing::info!(failures = self.failures, "long polling recovered");
+        }
+        self.failures = 0;
+        *self.health.lock().unwrap() = Health::Healthy;
+    }
+
+    /// Back off after a failed request.
+    fn failed(&mut self, health: Health) {
+        self.failures += 1;
+        let (health, delay) = if self.failures >= self.circuit_breaker {
+            if self.failures == self.circuit_breaker {
+                tracing::error!(failures = self.failures, "long polling circuit opened");
+            }
+            (Health::CircuitOpen, self.max_error_delay)
+        } else {
+            let delay = self.error_delay * 2u32.saturating_pow(self.failures - 1);
+            (health, min(delay, self.max_error_delay))
+        };
+        let delay = jitter(delay);
+        tracing::debug!(failures = self.failures, delay = ?delay, "backing off");
+
+        *self.health.lock().unwrap() = health;
+        self.delay = Some(self.api.runtime().sleep(delay));
+    }
+}
+
+impl HealthMonitor {
+    /// Returns the current health of the stream.
+    pub fn health(&self) -> Health {
+        *self.health.lock().unwrap()
+    }
+}
+
+/// Randomize `delay` by up to a half.
+fn jitter(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let half = delay / 2;
+    half + half * (random % 1024) as u32 / 1024
+}
+
+impl Acknowledger {
+    /// Acknowledge that the update `update_id` has been processed.
+    ///
+    /// Updates may be acknowledged in any order, the offset only advances
+    /// past an update once all updates received before it are acknowledged too.
+    /// Unknown and repeated ids are ignored.
+    pub fn ack(&self, update_id: Integer) -> Result<(), Error> {
+        let mut offsets = self.offsets.lock().unwrap();
+        if !offsets.unacked.contains(&update_id) {
+            return Ok(());
+        }
+        let acked = match offsets.unacked.iter().find(|&&id| id != update_id) {
+            Some(first) => first - 1,
+            None => offsets.received,
+        };
+        if acked > offsets.acked {
+            if let Some(ref store) = offsets.store {
+                store.store(acked)?;
+            }
+            offsets.acked = acked;
+        }
+        offsets.unacked.remove(&update_id);
+        if let Some(waker) = offsets.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::connector::cassette::ReplayConnector;
+    use crate::offset::MemoryOffsetStore;
+    use crate::runtime::{default_runtime, timeout};
+    use crate::test_support::{message_update, updates_api, Sleeps};
+
+    #[tokio::test]
+    async fn test_ack_mode() {
+        let (api, requests) = updates_api(&[message_update(6, "hi"), message_update(7, "hi")]);
+
+        let store = Arc::new(MemoryOffsetStore::new());
+        store.store(5).unwrap();
+        let mut stream = api.stream();
+        stream.offset_store(store.clone()).ack_mode(true);
+
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 6);
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 7);
+        let pending = timeout(
+            &*default_runtime(),
+            Duration::from_millis(50),
+            stream.next(),
+        )
+        .await;
+        assert!(pending.is_none());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(requests.lock().unwrap()[0].contains(r#""offset":6"#));
+
+        // Acknowledging a later update doesn't skip the earlier one.
+        stream.ack(7).unwrap();
+        assert_eq!(store.load().unwrap(), Some(5));
+        let pending = timeout(
+            &*default_runtime(),
+            Duration::from_millis(50),
+            stream.next(),
+        )
+        .await;
+        assert!(pending.is_none());
+
+        stream.ack(6).unwrap();
+        assert_eq!(store.load().unwrap(), Some(7));
+        assert_eq!(stream.next().await.unwrap().unwrap().id, 6);
+        assert!(requests.lock().unwrap()[1].contains(r#""offset":8"#));
+    }
+
+    #[tokio::test]
+    async fn test_backoff_and_circuit_breaker() {
+        let sleeps = Arc::new(Sleeps::new());
+        let connector = ReplayConnector::new(Vec::new()).strict(true);
+        let api = Api::builder("token")
+            .connector(Box::new(connector))
+            .runtime(sleeps.clone())
+            .build();
+        let mut stream = api.stream();
+        stream
+            .error_delay(Duration::from_millis(20))
+            .max_error_delay(Duration::from_secs(1))
+            .circuit_breaker(3);
+        let monitor = stream.health_monitor();
+
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(monitor.health(), Health::Degraded { failures: 1 });
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(monitor.health(), Health::Degraded { failures: 2 });
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(stream.health(), Health::CircuitOpen);
+        assert!(stream.next().await.unwrap().is_err());
+
+        // Requests are bounded by the long polling timeout, in between the delays double
+        // up to the circuit breaker and are randomized by up to a half.
+        let request_timeout = Duration::from_secs(
+            TELEGRAM_LONG_POLL_TIMEOUT_SECONDS + TELEGRAM_LONG_POLL_WATCHDOG_SECONDS,
+        );
+        let delays = sleeps.slept();
+        let delays: Vec<_> = delays
+            .into_iter()
+            .filter(|delay| *delay != request_timeout)
+            .collect();
+        let expected = [20, 40, 1000, 1000];
+        assert_eq!(delays.len(), expected.len());
+        for (delay, expected) in delays.into_iter().zip(&expected) {
+            let expected = Duration::from_millis(*expected);
+            assert!(delay >= expected / 2 && delay <= expected, "{:?}", delay);
+        }
+    }
 }
@@ -0,0 +1,132 @@
+//! Fixtures shared by the unit tests of the crate.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{future, Future};
+
+use crate::api::Api;
+use crate::connector::cassette::{Interaction, ReplayConnector};
+use crate::connector::{ByteStream, InspectLayer};
+use crate::errors::Error;
+use crate::runtime::{default_runtime, FileStream, Runtime};
+
+/// Returns an interaction answering the request of `method` with `body` by `response`.
+pub(crate) fn interaction(method: &str, body: &str, response: &str) -> Interaction {
+    Interaction {
+        method: method.into(),
+        url: String::new(),
+        body: body.into(),
+        response: Some(response.into()),
+    }
+}
+
+/// Returns a connector answering requests of every method with the paired response.
+pub(crate) fn replay_connector(responses: &[(&str, &str)]) -> ReplayConnector {
+    let interactions = responses
+        .iter()
+        .map(|&(method, response)| interaction(method, "", response))
+        .collect();
+    ReplayConnector::new(interactions)
+}
+
+/// Returns an `Api` answering requests of every method with the paired response.
+pub(crate) fn replay_api(responses: &[(&str, &str)]) -> Api {
+    Api::with_connector("token", Box::new(replay_connector(responses)))
+}
+
+/// Returns an `Api` answering every `getUpdates` request with `updates`,
+/// together with the bodies of all requests sent through it.
+pub(crate) fn updates_api(updates: &[String]) -> (Api, Arc<Mutex<Vec<String>>>) {
+    let response = format!(r#"{{"ok":true,"result":[{}]}}"#, updates.join(","));
+    let connector = replay_connector(&[("getUpdates", &response)]);
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+    let inspect = InspectLayer::new().request(move |request| {
+        recorded.lock().unwrap().push(request.body.to_string());
+    });
+    let api = Api::builder("token")
+        .connector(Box::new(connector))
+        .layer(inspect)
+        .build();
+    (api, requests)
+}
+
+/// Returns an update with the text message `text` in a private chat.
+pub(crate) fn message_update(update_id: i64, text: &str) -> String {
+    format!(
+        r#"{{"update_id":{},"message":{{"message_id":{},"date":0,
+            "chat":{{"id":1,"type":"private","first_name":"A"}},
+            "from":{{"id":1,"is_bot":false,"first_name":"A"}},"text":"{}"}}}}"#,
+        update_id, update_id, text
+    )
+}
+
+/// Runtime recording sleeps instead of waiting, everything else is done by the default runtime.
+pub(crate) struct Sleeps {
+    slept: Mutex<Vec<Duration>>,
+    on_sleep: Mutex<Option<Box<dyn Fn() + Send>>>,
+    inner: Arc<dyn Runtime>,
+}
+
+impl Sleeps {
+    pub(crate) fn new() -> Self {
+        Sleeps {
+            slept: Mutex::new(Vec::new()),
+            on_sleep: Mutex::new(None),
+            inner: default_runtime(),
+        }
+    }
+
+    /// Call `callback` whenever something sleeps.
+    pub(crate) fn on_sleep<F: Fn() + Send + 'static>(&self, callback: F) {
+        *self.on_sleep.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Returns the durations of all sleeps so far.
+    pub(crate) fn slept(&self) -> Vec<Duration> {
+        self.slept.lock().unwrap().clone()
+    }
+}
+
+impl fmt::Debug for Sleeps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sleeps")
+            .field("slept", &self.slept)
+            .finish()
+    }
+}
+
+impl Runtime for Sleeps {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        self.inner.spawn(future)
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.slept.lock().unwrap().push(duration);
+        if let Some(ref callback) = *self.on_sleep.lock().unwrap() {
+            callback();
+        }
+        Box::pin(future::ready(()))
+    }
+
+    fn read_file(&self, path: PathBuf) -> FileStream {
+        self.inner.read_file(path)
+    }
+
+    fn file_len(&self, path: PathBuf) -> Pin<Box<dyn Future<Output = io::Result<u64>> + Send>> {
+        self.inner.file_len(path)
+    }
+
+    fn write_file(
+        &self,
+        path: PathBuf,
+        contents: ByteStream,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>> {
+        self.inner.write_file(path, contents)
+    }
+}
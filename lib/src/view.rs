@@ -0,0 +1,113 @@
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+use telegram_bot_raw::{Integer, Message, UpdateKind};
+
+use crate::errors::Error;
+use crate::filter::Filter;
+use crate::stream::UpdatesStream;
+
+type Accept<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// Stream of updates of a single kind, created by [`UpdatesStream::messages`] and friends.
+///
+/// Other updates are skipped. In [ack mode] skipped updates are acknowledged right away,
+/// yielded ones as soon as the next item is requested.
+///
+/// [`UpdatesStream::messages`]: struct.UpdatesStream.html#method.messages
+/// [ack mode]: struct.UpdatesStream.html#method.ack_mode
+#[must_use = "streams do nothing unless polled"]
+pub struct UpdateView<T> {
+    stream: UpdatesStream,
+    extract: fn(UpdateKind) -> Option<T>,
+    accept: Option<Accept<T>>,
+    pending_ack: Option<Integer>,
+}
+
+impl<T> UpdateView<T> {
+    pub(crate) fn new(stream: UpdatesStream, extract: fn(UpdateKind) -> Option<T>) -> Self {
+        UpdateView {
+            stream,
+            extract,
+            accept: None,
+            pending_ack: None,
+        }
+    }
+
+    /// Returns the underlying stream, e.g. to read its health.
+    pub fn stream(&self) -> &UpdatesStream {
+        &self.stream
+    }
+}
+
+impl UpdateView<Message> {
+    /// Only yield messages matched by `filter`.
+    pub fn matching(mut self, filter: Filter) -> Self {
+        self.accept = Some(Box::new(move |message| filter.matches(message)));
+        self
+    }
+}
+
+impl<T> Stream for UpdateView<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let ref_mut = self.get_mut();
+        loop {
+            if let Some(update_id) = ref_mut.pending_ack.take() {
+                if let Err(err) = ref_mut.stream.ack(update_id) {
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+
+            let update = match Pin::new(&mut ref_mut.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(update))) => update,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            if ref_mut.stream.is_ack_mode() {
+                ref_mut.pending_ack = Some(update.id);
+            }
+
+            if let Some(item) = (ref_mut.extract)(update.kind) {
+                match ref_mut.accept {
+                    Some(ref accept) if !accept(&item) => {
+                        tracing::trace!("update rejected by filter");
+                    }
+                    _ => return Poll::Ready(Some(Ok(item))),
+                }
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for UpdateView<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UpdateView")
+            .field("pending_ack", &self.pending_ack)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::test_support::{message_update, updates_api};
+
+    #[tokio::test]
+    async fn test_messages_view() {
+        let (api, requests) =
+            updates_api(&[message_update(1, "hello"), message_update(2, "/start")]);
+
+        let mut messages = api.stream().messages().matching(Filter::command("start"));
+        let message = messages.next().await.unwrap().unwrap();
+        assert_eq!(message.id, 2.into());
+        assert!(requests.lock().unwrap()[0].contains(r#""allowed_updates":["message"]"#));
+    }
+}
@@ -0,0 +1,229 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+
+use futures::channel::{mpsc, oneshot};
+use futures::{FutureExt, SinkExt, Stream};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+
+use telegram_bot_raw::Update;
+
+use crate::api::Api;
+use crate::errors::{Error, ErrorKind};
+
+const TELEGRAM_WEBHOOK_DEFAULT_PATH: &str = "/";
+/// Number of received updates waiting to be polled, further requests wait for room.
+const WEBHOOK_QUEUE_SIZE: usize = 100;
+
+/// This type represents stream of Telegram API updates and uses
+/// webhooks under the hood: an HTTP server is started on the first poll
+/// and every update posted by Telegram is yielded from the stream.
+///
+/// The server only listens for plain HTTP, so it is expected to run behind
+/// a TLS-terminating reverse proxy. Requests with a malformed body are answered
+/// with 400 and logged, they never end up in the stream. When updates aren't polled,
+/// at most 100 of them are queued and Telegram's requests wait until there is room.
+/// Use [`SetWebhook`] to tell Telegram where to deliver updates.
+///
+/// The server is built on hyper, so it is spawned on the tokio runtime the stream
+/// is first polled from, whatever [`Runtime`] the `Api` uses.
+///
+/// [`SetWebhook`]: ../telegram_bot_raw/requests/set_webhook/struct.SetWebhook.html
+/// [`Runtime`]: runtime/trait.Runtime.html
+#[must_use = "streams do nothing unless polled"]
+pub struct Webhook {
+    api: Api,
+    addr: SocketAddr,
+    path: String,
+    state: WebhookState,
+}
+
+enum WebhookState {
+    Idle,
+    Running {
+        updates: mpsc::Receiver<Result<Update, Error>>,
+        _shutdown: oneshot::Sender<()>,
+    },
+    Finished,
+}
+
+impl Stream for Webhook {
+    type Item = Result<Update, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let ref_mut = self.get_mut();
+        let span = tracing::trace_span!("webhook", addr = %ref_mut.addr);
+        let _enter = span.enter();
+
+        if let WebhookState::Idle = ref_mut.state {
+            tracing::trace!("starting webhook server");
+            match ref_mut.start() {
+                Ok(state) => ref_mut.state = state,
+                Err(err) => {
+                    tracing::error!(error = %err, "unable to start webhook server");
+                    ref_mut.state = WebhookState::Finished;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+
+        match ref_mut.state {
+            WebhookState::Running {
+                ref mut updates, ..
+            } => match Pin::new(updates).poll_next(cx) {
+                Poll::Ready(Some(Ok(update))) => {
+                    ref_mut.api.observe_update(&update);
+                    Poll::Ready(Some(Ok(update)))
+                }
+                Poll::Ready(None) => {
+                    tracing::trace!("webhook server stopped");
+                    ref_mut.state = WebhookState::Finished;
+                    Poll::Ready(None)
+                }
+                poll => poll,
+            },
+            _ => Poll::Ready(None),
+        }
+    }
+}
+
+impl Webhook {
+    /// Create a new `Webhook` instance listening on `addr`.
+    pub fn new(api: &Api, addr: SocketAddr) -> Self {
+        Webhook {
+            api: api.clone(),
+            addr,
+            path: TELEGRAM_WEBHOOK_DEFAULT_PATH.to_string(),
+            state: WebhookState::Idle,
+        }
+    }
+
+    /// Set the path updates are posted to, requests to other paths are answered with 404.
+    /// Using a secret path, e.g. containing the bot token, makes sure
+    /// that updates are really coming from Telegram.
+    ///
+    /// Default path is `/`.
+    pub fn path<T: Into<String>>(&mut self, path: T) -> &mut Self {
+        self.path = path.into();
+        self
+    }
+
+    fn start(&self) -> Result<WebhookState, Error> {
+        let (sender, updates) = mpsc::channel(WEBHOOK_QUEUE_SIZE);
+        let (shutdown, on_shutdown) = oneshot::channel::<()>();
+        let path: Arc<str> = self.path.as_str().into();
+
+        let mut errors = sender.clone();
+        let make_service = make_service_fn(move |_| {
+            let path = path.clone();
+            let sender = sender.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |request| {
+                    handle(request, path.clone(), sender.clone())
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&self.addr)
+            .map_err(ErrorKind::from)?
+            .serve(make_service)
+            .with_graceful_shutdown(on_shutdown.map(|_| ()));
+
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                tracing::error!(error = %err, "webhook server error");
+                let _ = errors.send(Err(ErrorKind::from(err).into())).await;
+            }
+        });
+
+        Ok(WebhookState::Running {
+            updates,
+            _shutdown: shutdown,
+        })
+    }
+}
+
+async fn handle(
+    request: Request<Body>,
+    path: Arc<str>,
+    mut sender: mpsc::Sender<Result<Update, Error>>,
+) -> Result<Response<Body>, hyper::Error> {
+    if request.method() != Method::POST || request.uri().path() != &*path {
+        return Ok(status_response(StatusCode::NOT_FOUND));
+    }
+
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+    let update = match serde_json::from_slice::<Update>(&body) {
+        Ok(update) => update,
+        Err(err) => {
+            tracing::warn!(error = %err, "unable to decode update");
+            return Ok(status_response(StatusCode::BAD_REQUEST));
+        }
+    };
+    tracing::trace!(update = ?update, "update received");
+
+    if sender.send(Ok(update)).await.is_err() {
+        return Ok(status_response(StatusCode::SERVICE_UNAVAILABLE));
+    }
+    Ok(status_response(StatusCode::OK))
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use hyper::Client;
+
+    use super::*;
+    use crate::runtime::{default_runtime, timeout};
+
+    async fn post(addr: SocketAddr, path: &str, body: &str) -> StatusCode {
+        let request = Request::post(format!("http://{}{}", addr, path))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        Client::new().request(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let api = Api::new("token");
+        let mut webhook = api.webhook(addr);
+        webhook.path("/secret");
+
+        // The first poll starts the server.
+        let pending = timeout(
+            &*default_runtime(),
+            Duration::from_millis(50),
+            webhook.next(),
+        )
+        .await;
+        assert!(pending.is_none());
+
+        assert_eq!(post(addr, "/", "{}").await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            post(addr, "/secret", "garbage").await,
+            StatusCode::BAD_REQUEST
+        );
+        let update = r#"{"update_id":7,"message":{"message_id":1,"date":0,
+            "chat":{"id":1,"type":"private","first_name":"A"},
+            "from":{"id":1,"is_bot":false,"first_name":"A"},"text":"hi"}}"#;
+        assert_eq!(post(addr, "/secret", update).await, StatusCode::OK);
+
+        assert_eq!(webhook.next().await.unwrap().unwrap().id, 7);
+    }
+}
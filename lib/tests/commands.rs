@@ -0,0 +1,97 @@
+#![cfg(feature = "macros")]
+
+use telegram_bot::types::BotCommand;
+use telegram_bot::{BotCommands, ParseCommandError};
+
+#[derive(BotCommands, Debug, PartialEq)]
+#[command(rename_all = "snake_case")]
+enum Command {
+    /// Show the help
+    #[command(alias = "h", alias = "about")]
+    Help,
+    /// Set the reminder
+    SetReminder { minutes: u32, text: String },
+    /// Roll some dice
+    #[command(rename = "roll")]
+    Dice(u8, u8),
+    #[command(hide)]
+    Debug,
+}
+
+#[derive(BotCommands, Debug, PartialEq)]
+#[command(case_insensitive)]
+enum Loose {
+    /// Start the bot
+    #[command(alias = "Hello")]
+    Start,
+}
+
+#[test]
+fn parse() {
+    assert_eq!(Command::parse("/help", "my_bot"), Ok(Command::Help));
+    assert_eq!(Command::parse("/about@my_bot", "my_bot"), Ok(Command::Help));
+    assert_eq!(
+        Command::parse("/set_reminder 5 take a break", "my_bot"),
+        Ok(Command::SetReminder {
+            minutes: 5,
+            text: "take a break".into()
+        })
+    );
+    assert_eq!(
+        Command::parse("/roll 2 6", "my_bot"),
+        Ok(Command::Dice(2, 6))
+    );
+    assert_eq!(Command::parse("/debug", "my_bot"), Ok(Command::Debug));
+    assert_eq!(
+        Command::parse("/HELP", "my_bot"),
+        Err(ParseCommandError::UnknownCommand("HELP".into()))
+    );
+
+    assert_eq!(Loose::parse("/START", "my_bot"), Ok(Loose::Start));
+    assert_eq!(Loose::parse("/hello", "my_bot"), Ok(Loose::Start));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Command::parse("help", "my_bot"),
+        Err(ParseCommandError::NotACommand)
+    );
+    assert_eq!(
+        Command::parse("/help@other_bot", "my_bot"),
+        Err(ParseCommandError::OtherBot("other_bot".into()))
+    );
+    assert_eq!(
+        Command::parse("/roll 2", "my_bot"),
+        Err(ParseCommandError::MissingArgument {
+            command: "roll".into(),
+            argument: "u8"
+        })
+    );
+    assert!(matches!(
+        Command::parse("/roll 2 x", "my_bot"),
+        Err(ParseCommandError::InvalidArgument { .. })
+    ));
+    assert_eq!(
+        Command::parse("/help me", "my_bot"),
+        Err(ParseCommandError::TooManyArguments {
+            command: "help".into()
+        })
+    );
+}
+
+#[test]
+fn bot_commands() {
+    assert_eq!(
+        Command::bot_commands(),
+        vec![
+            BotCommand::new("help", "Show the help"),
+            BotCommand::new("set_reminder", "Set the reminder"),
+            BotCommand::new("roll", "Roll some dice"),
+        ]
+    );
+    assert_eq!(
+        Command::help(),
+        "/help - Show the help\n/set_reminder <minutes> <text> - Set the reminder\n/roll <u8> <u8> - Roll some dice"
+    );
+}
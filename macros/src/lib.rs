@@ -0,0 +1,349 @@
+//! Derive macros for [telegram-bot](https://docs.rs/telegram-bot/).
+//!
+//! Use the macros through their re-exports in `telegram-bot`.
+
+extern crate proc_macro;
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta,
+    Type,
+};
+
+/// Derive `telegram_bot::BotCommands` for an enum, see `telegram_bot::commands`.
+#[proc_macro_derive(BotCommands, attributes(command))]
+pub fn derive_bot_commands(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match bot_commands(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Item the `#[command(..)]` attribute is placed on.
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    Enum,
+    Variant,
+}
+
+#[derive(Default)]
+struct Options {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    aliases: Vec<String>,
+    description: Option<String>,
+    case_insensitive: bool,
+    hide: bool,
+}
+
+struct Command {
+    names: Vec<String>,
+    description: String,
+    hide: bool,
+    arguments: Vec<String>,
+    parse: TokenStream2,
+}
+
+fn bot_commands(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "BotCommands can only be derived for enums",
+            ))
+        }
+    };
+    let options = options(&input.attrs, Scope::Enum)?;
+    let rename_all = options.rename_all.as_deref().unwrap_or("lowercase");
+
+    let mut commands = Vec::new();
+    let mut variants = HashMap::new();
+    for variant in &data.variants {
+        let variant_options = options_with_docs(&variant.attrs)?;
+        let name = match variant_options.rename {
+            Some(ref name) => name.clone(),
+            None => rename(&variant.ident, rename_all)?,
+        };
+        let mut names = vec![name];
+        names.extend(variant_options.aliases.iter().cloned());
+        if options.case_insensitive {
+            names
+                .iter_mut()
+                .for_each(|name| *name = name.to_lowercase());
+        }
+        for name in &names {
+            validate_name(&variant.ident, name)?;
+            if let Some(other) = variants.insert(name.clone(), &variant.ident) {
+                let message = format!("command /{} is already used by {}", name, other);
+                return Err(Error::new_spanned(&variant.ident, message));
+            }
+        }
+        let description = variant_options.description.unwrap_or_default();
+        if !variant_options.hide {
+            validate_description(&variant.ident, &description)?;
+        }
+
+        let ident = &variant.ident;
+        let (arguments, parse) = match variant.fields {
+            Fields::Unit => (Vec::new(), quote!(Self::#ident)),
+            Fields::Unnamed(ref fields) => {
+                let count = fields.unnamed.len();
+                let arguments = fields
+                    .unnamed
+                    .iter()
+                    .map(|field| type_name(&field.ty))
+                    .collect::<Vec<_>>();
+                let values = fields.unnamed.iter().zip(&arguments).enumerate().map(
+                    |(index, (field, argument))| {
+                        argument_value(&field.ty, argument, index + 1 == count)
+                    },
+                );
+                (arguments.clone(), quote!(Self::#ident(#(#values),*)))
+            }
+            Fields::Named(ref fields) => {
+                let count = fields.named.len();
+                let arguments = fields
+                    .named
+                    .iter()
+                    .map(|field| field.ident.as_ref().unwrap().to_string())
+                    .collect::<Vec<_>>();
+                let values = fields.named.iter().zip(&arguments).enumerate().map(
+                    |(index, (field, argument))| {
+                        let name = &field.ident;
+                        let value = argument_value(&field.ty, argument, index + 1 == count);
+                        quote!(#name: #value)
+                    },
+                );
+                (arguments.clone(), quote!(Self::#ident { #(#values),* }))
+            }
+        };
+
+        commands.push(Command {
+            names,
+            description,
+            hide: variant_options.hide,
+            arguments,
+            parse,
+        });
+    }
+
+    let arms = commands.iter().map(|command| {
+        let names = &command.names;
+        let parse = &command.parse;
+        quote! {
+            #(#names)|* => {
+                let command = #parse;
+                arguments.finish()?;
+                ::std::result::Result::Ok(command)
+            }
+        }
+    });
+    let visible = commands.iter().filter(|command| !command.hide);
+    let bot_commands = visible.clone().map(|command| {
+        let name = &command.names[0];
+        let description = &command.description;
+        quote!(::telegram_bot::types::BotCommand::new(#name, #description))
+    });
+    let help = visible
+        .map(|command| {
+            let mut line = format!("/{}", command.names[0]);
+            for argument in &command.arguments {
+                line.push_str(&format!(" <{}>", argument));
+            }
+            if !command.description.is_empty() {
+                line.push_str(&format!(" - {}", command.description));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let matched = if options.case_insensitive {
+        quote!(&*name.to_lowercase())
+    } else {
+        quote!(name)
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::telegram_bot::BotCommands for #ident #ty_generics #where_clause {
+            fn parse(
+                text: &str,
+                username: &str,
+            ) -> ::std::result::Result<Self, ::telegram_bot::ParseCommandError> {
+                #[allow(unused_mut)]
+                let (name, mut arguments) = ::telegram_bot::commands::split_command(text, username)?;
+                match #matched {
+                    #(#arms)*
+                    _ => ::std::result::Result::Err(
+                        ::telegram_bot::ParseCommandError::UnknownCommand(name.to_string()),
+                    ),
+                }
+            }
+
+            fn bot_commands() -> ::std::vec::Vec<::telegram_bot::types::BotCommand> {
+                ::std::vec![#(#bot_commands),*]
+            }
+
+            fn help() -> ::std::string::String {
+                ::std::string::String::from(#help)
+            }
+        }
+    })
+}
+
+/// Parse the `#[command(..)]` attributes, rejecting options which don't apply to `scope`.
+fn options(attrs: &[Attribute], scope: Scope) -> Result<Options, Error> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("command")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected #[command(..)]")),
+        };
+        for nested in list.nested {
+            check_scope(&nested, scope)?;
+            match nested {
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("case_insensitive") => {
+                    options.case_insensitive = true
+                }
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("hide") => {
+                    options.hide = true
+                }
+                NestedMeta::Meta(Meta::NameValue(ref pair)) => {
+                    let value = match pair.lit {
+                        Lit::Str(ref value) => value.value(),
+                        ref lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    };
+                    if pair.path.is_ident("rename") {
+                        options.rename = Some(value);
+                    } else if pair.path.is_ident("rename_all") {
+                        options.rename_all = Some(value);
+                    } else if pair.path.is_ident("alias") {
+                        options.aliases.push(value);
+                    } else if pair.path.is_ident("description") {
+                        options.description = Some(value);
+                    } else {
+                        return Err(Error::new_spanned(&pair.path, "unknown command option"));
+                    }
+                }
+                nested => return Err(Error::new_spanned(nested, "unknown command option")),
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Parse the `#[command(..)]` attributes, the description defaults to the doc comment.
+fn options_with_docs(attrs: &[Attribute]) -> Result<Options, Error> {
+    let mut options = options(attrs, Scope::Variant)?;
+    if options.description.is_none() {
+        let docs = attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::NameValue(pair)) => match pair.lit {
+                    Lit::Str(value) => Some(value.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !docs.is_empty() {
+            options.description = Some(docs.join(" "));
+        }
+    }
+    Ok(options)
+}
+
+fn check_scope(nested: &NestedMeta, scope: Scope) -> Result<(), Error> {
+    let path = match nested {
+        NestedMeta::Meta(meta) => meta.path(),
+        NestedMeta::Lit(_) => return Ok(()),
+    };
+    let allowed = match path.get_ident().map(Ident::to_string).as_deref() {
+        Some("rename_all") | Some("case_insensitive") => Scope::Enum,
+        Some("rename") | Some("alias") | Some("description") | Some("hide") => Scope::Variant,
+        _ => return Ok(()),
+    };
+    if allowed != scope {
+        let message = match allowed {
+            Scope::Enum => "this command option is only allowed on the enum",
+            Scope::Variant => "this command option is only allowed on variants",
+        };
+        return Err(Error::new_spanned(path, message));
+    }
+    Ok(())
+}
+
+fn rename(ident: &Ident, rule: &str) -> Result<String, Error> {
+    let name = ident.to_string();
+    match rule {
+        "lowercase" => Ok(name.to_lowercase()),
+        "snake_case" => {
+            let mut snake = String::new();
+            for (index, ch) in name.chars().enumerate() {
+                if ch.is_uppercase() && index > 0 {
+                    snake.push('_');
+                }
+                snake.extend(ch.to_lowercase());
+            }
+            Ok(snake)
+        }
+        _ => Err(Error::new(
+            Span::call_site(),
+            "rename_all must be \"lowercase\" or \"snake_case\"",
+        )),
+    }
+}
+
+/// Telegram only accepts command names of 1 to 32 lowercase letters, digits and underscores.
+fn validate_name<T: ToTokens>(ident: T, name: &str) -> Result<(), Error> {
+    let valid = name
+        .chars()
+        .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_');
+    if name.is_empty() || name.len() > 32 || !valid {
+        let message = format!(
+            "invalid command name {:?}, expected 1 to 32 lowercase letters, digits and underscores",
+            name
+        );
+        return Err(Error::new_spanned(ident, message));
+    }
+    Ok(())
+}
+
+/// Telegram only accepts command descriptions of 3 to 256 characters.
+fn validate_description<T: ToTokens>(ident: T, description: &str) -> Result<(), Error> {
+    let len = description.chars().count();
+    if !(3..=256).contains(&len) {
+        let message = format!(
+            "command description must be 3 to 256 characters long, got {}, \
+             set it with a doc comment or #[command(description = \"..\")], or use #[command(hide)]",
+            len
+        );
+        return Err(Error::new_spanned(ident, message));
+    }
+    Ok(())
+}
+
+fn type_name(ty: &Type) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
+/// Returns the expression parsing the argument of type `ty`, a trailing `String` takes the rest.
+fn argument_value(ty: &Type, argument: &str, last: bool) -> TokenStream2 {
+    let is_string = match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident("String"),
+        _ => false,
+    };
+    if last && is_string {
+        quote!(arguments.rest(#argument)?)
+    } else {
+        quote!(arguments.next::<#ty>(#argument)?)
+    }
+}
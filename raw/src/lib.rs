@@ -2,9 +2,11 @@
 extern crate serde_derive;
 
 pub mod requests;
+pub mod token;
 pub mod types;
 pub mod url;
 
 pub use crate::requests::*;
+pub use crate::token::*;
 pub use crate::types::*;
 pub use crate::url::*;
@@ -1,20 +1,124 @@
 use std::error;
 use std::fmt;
+use std::time::Duration;
 
 use crate::types::*;
 
 #[derive(Debug)]
 pub struct Error(ErrorKind);
 
+/// Classification of an error, see [`Error::category`](struct.Error.html#method.category).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Connection to the server failed or was interrupted.
+    Network,
+    /// Request to the server timed out.
+    Timeout,
+    /// HTTP request couldn't be built or the server answered with an unexpected response.
+    Http,
+    /// Request or response couldn't be serialized or deserialized.
+    Json,
+    /// Flood control exceeded, the request can be repeated after the duration.
+    FloodWait(Duration),
+    /// The group has been migrated to the supergroup.
+    ChatMigrated(SupergroupId),
+    /// The bot is not allowed to act in the chat, e.g. it was blocked by the user
+    /// or kicked from the group.
+    Forbidden,
+    /// Request was rejected as invalid, e.g. the message to edit doesn't exist.
+    BadRequest,
+    /// Requested method doesn't exist.
+    NotFound,
+    /// Bot token is invalid or was revoked.
+    Unauthorized,
+    /// Any other error.
+    Other,
+}
+
 #[derive(Debug)]
 pub(crate) enum ErrorKind {
     EmptyBody,
     TelegramError {
+        error_code: Option<Integer>,
         description: String,
         parameters: Option<ResponseParameters>,
     },
     DetachedError(String),
     Json(::serde_json::Error),
+    CallbackDataTooLong(usize),
+}
+
+impl Error {
+    /// Returns the classification of the error.
+    pub fn category(&self) -> ErrorCategory {
+        match &self.0 {
+            ErrorKind::EmptyBody => ErrorCategory::Http,
+            ErrorKind::TelegramError {
+                error_code,
+                parameters,
+                ..
+            } => {
+                let parameters = parameters.as_ref();
+                if let Some(chat_id) = parameters.and_then(|p| p.migrate_to_chat_id) {
+                    return ErrorCategory::ChatMigrated(SupergroupId::new(chat_id));
+                }
+                if let Some(seconds) = parameters.and_then(|p| p.retry_after) {
+                    return ErrorCategory::FloodWait(Duration::from_secs(seconds.max(0) as u64));
+                }
+                match error_code {
+                    Some(400) => ErrorCategory::BadRequest,
+                    Some(401) => ErrorCategory::Unauthorized,
+                    Some(403) => ErrorCategory::Forbidden,
+                    Some(404) => ErrorCategory::NotFound,
+                    Some(429) => ErrorCategory::FloodWait(Duration::from_secs(0)),
+                    _ => ErrorCategory::Other,
+                }
+            }
+            ErrorKind::DetachedError(_) => ErrorCategory::Other,
+            ErrorKind::Json(_) => ErrorCategory::Json,
+            ErrorKind::CallbackDataTooLong(_) => ErrorCategory::Other,
+        }
+    }
+
+    /// Returns the numeric error code returned by the Telegram server.
+    pub fn error_code(&self) -> Option<Integer> {
+        match &self.0 {
+            ErrorKind::TelegramError { error_code, .. } => *error_code,
+            _ => None,
+        }
+    }
+
+    /// Returns the human-readable description returned by the Telegram server.
+    pub fn description(&self) -> Option<&str> {
+        match &self.0 {
+            ErrorKind::TelegramError { description, .. } => Some(description),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of seconds left to wait before the request can be repeated,
+    /// if the request was rejected by the flood control.
+    pub fn retry_after(&self) -> Option<Integer> {
+        match &self.0 {
+            ErrorKind::TelegramError {
+                parameters: Some(parameters),
+                ..
+            } => parameters.retry_after,
+            _ => None,
+        }
+    }
+
+    /// Returns the identifier of the supergroup the group has been migrated to,
+    /// if the request was rejected because of the migration.
+    pub fn migrate_to_chat_id(&self) -> Option<Integer> {
+        match &self.0 {
+            ErrorKind::TelegramError {
+                parameters: Some(parameters),
+                ..
+            } => parameters.migrate_to_chat_id,
+            _ => None,
+        }
+    }
 }
 
 impl From<::serde_json::Error> for ErrorKind {
@@ -36,6 +140,7 @@ impl fmt::Display for Error {
             ErrorKind::TelegramError {
                 description,
                 parameters,
+                ..
             } => {
                 f.write_str(&description)?;
                 if let Some(parameters) = parameters {
@@ -50,6 +155,11 @@ impl fmt::Display for Error {
             }
             ErrorKind::DetachedError(s) => f.write_str(&s),
             ErrorKind::Json(error) => write!(f, "{}", error),
+            ErrorKind::CallbackDataTooLong(len) => write!(
+                f,
+                "callback data is {} bytes long, the limit is {} bytes",
+                len, MAX_CALLBACK_DATA_LEN
+            ),
         }
     }
 }
@@ -2,12 +2,15 @@ use std::fmt;
 
 use bytes::Bytes;
 
-use crate::types::Text;
-use crate::url::telegram_api_url;
+use crate::token::BotToken;
+use crate::types::{ChatRef, Integer, Text};
+use crate::url::ApiUrl;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum RequestUrl {
     Method(&'static str),
+    /// File to download, with the path returned by `getFile`.
+    File(String),
 }
 
 impl RequestUrl {
@@ -15,9 +18,14 @@ impl RequestUrl {
         RequestUrl::Method(method)
     }
 
-    pub fn url(&self, token: &str) -> String {
+    pub fn file<T: Into<String>>(file_path: T) -> Self {
+        RequestUrl::File(file_path.into())
+    }
+
+    pub fn url(&self, api_url: &ApiUrl, token: &BotToken) -> String {
         match self {
-            &RequestUrl::Method(method) => format!("{}bot{}/{}", telegram_api_url(), token, method),
+            &RequestUrl::Method(method) => api_url.method_url(token, method),
+            RequestUrl::File(file_path) => api_url.file_url(token, file_path),
         }
     }
 }
@@ -68,6 +76,56 @@ impl HttpRequest {
     pub fn name(&self) -> &'static str {
         match self.url {
             RequestUrl::Method(method) => method,
+            RequestUrl::File(_) => "downloadFile",
+        }
+    }
+
+    /// Returns the chat targeted by the request, read from its `chat_id` parameter.
+    pub fn chat_ref(&self) -> Option<ChatRef> {
+        #[derive(Deserialize)]
+        struct Target {
+            chat_id: Option<ChatRef>,
+        }
+
+        match &self.body {
+            Body::Json(body) => serde_json::from_str::<Target>(body).ok()?.chat_id,
+            Body::Multipart(parts) => parts.iter().find_map(|(key, value)| match value {
+                MultipartValue::Text(text) if *key == "chat_id" => {
+                    Some(match text.as_str().parse::<Integer>() {
+                        Ok(id) => ChatRef::Id(id.into()),
+                        Err(_) => ChatRef::ChannelUsername(text.as_str().to_string()),
+                    })
+                }
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Replace the `chat_id` parameter of the request,
+    /// returns `false` if the request doesn't target a chat.
+    pub fn set_chat_ref(&mut self, chat: &ChatRef) -> bool {
+        match &mut self.body {
+            Body::Json(body) => {
+                let mut value = match serde_json::from_str::<serde_json::Value>(body) {
+                    Ok(serde_json::Value::Object(value)) => value,
+                    _ => return false,
+                };
+                match value.get_mut("chat_id") {
+                    Some(chat_id) => *chat_id = serde_json::json!(chat),
+                    None => return false,
+                }
+                *body = serde_json::Value::Object(value).to_string();
+                true
+            }
+            Body::Multipart(parts) => {
+                match parts.iter_mut().find(|(key, _)| *key == "chat_id") {
+                    Some((_, value)) => *value = MultipartValue::Text(chat.to_string().into()),
+                    None => return false,
+                }
+                true
+            }
+            _ => false,
         }
     }
 }
@@ -2,7 +2,7 @@ mod _base;
 pub use self::_base::*;
 
 mod errors;
-pub use self::errors::Error;
+pub use self::errors::{Error, ErrorCategory};
 pub(crate) use self::errors::ErrorKind;
 
 mod http;
@@ -0,0 +1,18 @@
+use crate::requests::*;
+
+/// Use this method to close the bot instance before moving it from one local server to another.
+/// You need to delete the webhook before calling this method to ensure that the bot isn't launched
+/// again after server restart. The method will return error 429 in the first 10 minutes
+/// after the bot is launched.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct Close;
+
+impl Request for Close {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("close"), self)
+    }
+}
@@ -0,0 +1,16 @@
+use crate::requests::*;
+use crate::types::*;
+
+/// Use this method to get the current list of the bot's commands.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct GetMyCommands;
+
+impl Request for GetMyCommands {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonIdResponse<Vec<BotCommand>>;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("getMyCommands"), self)
+    }
+}
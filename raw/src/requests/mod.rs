@@ -2,7 +2,9 @@
 pub mod _base;
 pub mod answer_callback_query;
 pub mod answer_inline_query;
+pub mod close;
 pub mod delete_message;
+pub mod delete_webhook;
 pub mod edit_message_caption;
 pub mod edit_message_live_location;
 pub mod edit_message_reply_markup;
@@ -15,10 +17,13 @@ pub mod get_chat_member;
 pub mod get_chat_members_count;
 pub mod get_file;
 pub mod get_me;
+pub mod get_my_commands;
 pub mod get_updates;
 pub mod get_user_profile_photos;
+pub mod get_webhook_info;
 pub mod kick_chat_member;
 pub mod leave_chat;
+pub mod log_out;
 pub mod pin_chat_message;
 pub mod send_audio;
 pub mod send_chat_action;
@@ -29,6 +34,8 @@ pub mod send_message;
 pub mod send_photo;
 pub mod send_venue;
 pub mod send_video;
+pub mod set_my_commands;
+pub mod set_webhook;
 pub mod stop_message_live_location;
 pub mod unban_chat_member;
 pub mod unpin_chat_message;
@@ -36,7 +43,9 @@ pub mod unpin_chat_message;
 pub use self::_base::*;
 pub use self::answer_callback_query::*;
 pub use self::answer_inline_query::*;
+pub use self::close::*;
 pub use self::delete_message::*;
+pub use self::delete_webhook::*;
 pub use self::edit_message_caption::*;
 pub use self::edit_message_live_location::*;
 pub use self::edit_message_reply_markup::*;
@@ -49,10 +58,13 @@ pub use self::get_chat_member::*;
 pub use self::get_chat_members_count::*;
 pub use self::get_file::*;
 pub use self::get_me::*;
+pub use self::get_my_commands::*;
 pub use self::get_updates::*;
 pub use self::get_user_profile_photos::*;
+pub use self::get_webhook_info::*;
 pub use self::kick_chat_member::*;
 pub use self::leave_chat::*;
+pub use self::log_out::*;
 pub use self::pin_chat_message::*;
 pub use self::send_audio::*;
 pub use self::send_chat_action::*;
@@ -63,6 +75,8 @@ pub use self::send_message::*;
 pub use self::send_photo::*;
 pub use self::send_venue::*;
 pub use self::send_video::*;
+pub use self::set_my_commands::*;
+pub use self::set_webhook::*;
 pub use self::stop_message_live_location::*;
 pub use self::unban_chat_member::*;
 pub use self::unpin_chat_message::*;
@@ -0,0 +1,24 @@
+use crate::requests::*;
+use crate::types::*;
+
+/// Use this method to change the list of the bot's commands.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct SetMyCommands {
+    commands: Vec<BotCommand>,
+}
+
+impl Request for SetMyCommands {
+    type Type = JsonRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("setMyCommands"), self)
+    }
+}
+
+impl SetMyCommands {
+    pub fn new(commands: Vec<BotCommand>) -> Self {
+        SetMyCommands { commands }
+    }
+}
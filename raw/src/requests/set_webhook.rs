@@ -0,0 +1,71 @@
+use crate::requests::*;
+use crate::types::*;
+
+/// Use this method to specify a url and receive incoming updates via an outgoing webhook.
+/// Whenever there is an update for the bot, Telegram will send an HTTPS POST request
+/// to the specified url, containing a JSON-serialized Update.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[must_use = "requests do nothing unless sent"]
+pub struct SetWebhook {
+    url: String,
+    #[serde(skip)]
+    certificate: Option<InputFile>,
+    max_connections: Option<Integer>,
+    allowed_updates: Option<Vec<AllowedUpdate>>,
+}
+
+impl ToMultipart for SetWebhook {
+    fn to_multipart(&self) -> Result<Multipart, Error> {
+        multipart_map! {
+            self,
+            (url (text));
+            (certificate (raw), optional);
+            (max_connections (text), optional);
+            (allowed_updates (json), optional);
+        }
+    }
+}
+
+impl Request for SetWebhook {
+    type Type = MultipartRequestType<Self>;
+    type Response = JsonTrueToUnitResponse;
+
+    fn serialize(&self) -> Result<HttpRequest, Error> {
+        Self::Type::serialize(RequestUrl::method("setWebhook"), self)
+    }
+}
+
+impl SetWebhook {
+    pub fn new<T>(url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            certificate: None,
+            max_connections: None,
+            allowed_updates: None,
+        }
+    }
+
+    /// Upload your public key certificate so that the root certificate in use can be checked.
+    pub fn certificate<V>(&mut self, certificate: V) -> &mut Self
+    where
+        V: Into<InputFileUpload>,
+    {
+        self.certificate = Some(InputFile::from(certificate.into()));
+        self
+    }
+
+    /// Maximum allowed number of simultaneous HTTPS connections to the webhook
+    /// for update delivery, 1-100. Defaults to 40.
+    pub fn max_connections(&mut self, max_connections: Integer) -> &mut Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn allowed_updates(&mut self, updates: &[AllowedUpdate]) -> &mut Self {
+        self.allowed_updates = Some(updates.to_vec());
+        self
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+const REDACTED: &str = "***";
+
+/// Authentication token of a bot, as issued by [@BotFather](https://t.me/botfather).
+///
+/// The token is a secret: `Debug` and `Display` only show the id of the bot,
+/// e.g. `123456:***`. Use [`expose`](#method.expose) to get the token itself.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BotToken(String);
+
+impl BotToken {
+    /// Create a new `BotToken`.
+    pub fn new<T: Into<String>>(token: T) -> Self {
+        BotToken(token.into())
+    }
+
+    /// Returns the secret token.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the id of the bot, which is the part of the token before the colon.
+    pub fn bot_id(&self) -> Option<&str> {
+        let end = self.0.find(':')?;
+        let id = &self.0[..end];
+        if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for BotToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bot_id() {
+            Some(id) => write!(f, "{}:{}", id, REDACTED),
+            None => f.write_str(REDACTED),
+        }
+    }
+}
+
+impl fmt::Debug for BotToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BotToken")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl From<String> for BotToken {
+    fn from(token: String) -> Self {
+        BotToken(token)
+    }
+}
+
+impl<'a> From<&'a String> for BotToken {
+    fn from(token: &'a String) -> Self {
+        BotToken(token.clone())
+    }
+}
+
+impl<'a> From<&'a str> for BotToken {
+    fn from(token: &'a str) -> Self {
+        BotToken(token.to_string())
+    }
+}
@@ -0,0 +1,22 @@
+/// This object represents a bot command.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct BotCommand {
+    /// Text of the command, 1-32 characters.
+    /// Can contain only lowercase English letters, digits and underscores.
+    pub command: String,
+    /// Description of the command, 3-256 characters.
+    pub description: String,
+}
+
+impl BotCommand {
+    pub fn new<C, D>(command: C, description: D) -> Self
+    where
+        C: Into<String>,
+        D: Into<String>,
+    {
+        BotCommand {
+            command: command.into(),
+            description: description.into(),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use serde::de::{Deserialize, Deserializer, Error};
 
+use crate::token::BotToken;
 use crate::types::*;
 use crate::url::*;
 
@@ -924,10 +925,10 @@ pub struct File {
 }
 
 impl File {
-    pub fn get_url(&self, token: &str) -> Option<String> {
+    pub fn get_url(&self, api_url: &ApiUrl, token: &BotToken) -> Option<String> {
         self.file_path
             .as_ref()
-            .map(|path| format!("{}file/bot{}/{}", telegram_api_url(), token, path))
+            .map(|path| api_url.file_url(token, path))
     }
 }
 
@@ -1,3 +1,4 @@
+pub mod bot_command;
 pub mod callback_query;
 pub mod chat;
 pub mod chat_member;
@@ -11,7 +12,9 @@ pub mod reply_markup;
 pub mod response_parameters;
 pub mod text;
 pub mod update;
+pub mod webhook_info;
 
+pub use self::bot_command::*;
 pub use self::callback_query::*;
 pub use self::chat::*;
 pub use self::chat_member::*;
@@ -25,3 +28,4 @@ pub use self::reply_markup::*;
 pub use self::response_parameters::*;
 pub use self::text::*;
 pub use self::update::*;
+pub use self::webhook_info::*;
@@ -360,6 +360,12 @@ pub struct FileRef {
     pub(crate) inner: String,
 }
 
+impl ToFileRef for FileRef {
+    fn to_file_ref(&self) -> FileRef {
+        self.clone()
+    }
+}
+
 impl<'a> From<&'a str> for FileRef {
     fn from(s: &'a str) -> Self {
         FileRef {
@@ -1,7 +1,19 @@
 use std::ops::Not;
 
+use crate::requests::{Error, ErrorKind};
 use crate::types::*;
 
+/// Maximal length of callback data accepted by Telegram in bytes.
+pub const MAX_CALLBACK_DATA_LEN: usize = 64;
+
+/// Fails if `data` is longer than Telegram accepts as callback data.
+pub fn check_callback_data(data: &str) -> Result<(), Error> {
+    if data.len() > MAX_CALLBACK_DATA_LEN {
+        return Err(ErrorKind::CallbackDataTooLong(data.len()).into());
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ReplyMarkup {
@@ -233,12 +245,26 @@ pub struct InlineKeyboardButton {
 }
 
 impl InlineKeyboardButton {
-    /// Data to be sent in a callback query to the bot when button is pressed, 1-64 bytes
+    /// Data to be sent in a callback query to the bot when button is pressed, 1-64 bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `callback` is longer than 64 bytes, use [`try_callback`](#method.try_callback)
+    /// for data which isn't known to fit.
     pub fn callback<T: AsRef<str>, C: AsRef<str>>(text: T, callback: C) -> Self {
-        Self {
+        match Self::try_callback(text, callback) {
+            Ok(button) => button,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Like [`callback`](#method.callback), but fails if `callback` is longer than 64 bytes.
+    pub fn try_callback<T: AsRef<str>, C: AsRef<str>>(text: T, callback: C) -> Result<Self, Error> {
+        check_callback_data(callback.as_ref())?;
+        Ok(Self {
             text: text.as_ref().to_string(),
             kind: InlineKeyboardButtonKind::CallbackData(callback.as_ref().to_string()),
-        }
+        })
     }
 
     pub fn url<T: AsRef<str>, U: AsRef<str>>(text: T, url: U) -> Self {
@@ -254,10 +280,10 @@ pub enum InlineKeyboardButtonKind {
     #[serde(rename = "url")]
     Url(String), // TODO(knsd): Url?
     #[serde(rename = "callback_data")]
-    CallbackData(String), // TODO(knsd) Validate size?
-                          //  SwitchInlineQuery(String),
-                          //  SwitchInlineQueryCurrentChat(String),
-                          //  CallbackGame(CallbackGame),
+    CallbackData(String),
+    //  SwitchInlineQuery(String),
+    //  SwitchInlineQueryCurrentChat(String),
+    //  CallbackGame(CallbackGame),
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
@@ -266,7 +292,7 @@ struct InlineKeyboardButtonRaw<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     url: Option<&'a str>, // TODO(knsd): Url?
     #[serde(skip_serializing_if = "Option::is_none")]
-    callback_data: Option<&'a str>, //TODO(knsd) Validate size?
+    callback_data: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     switch_inline_query: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
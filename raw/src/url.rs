@@ -1,7 +1,8 @@
 use std::env;
 
-const TELEGRAM_API_URL_DEFAULT: &str = "http://127.0.0.1:30001/api/bot/";
-// const TELEGRAM_API_URL_DEFAULT: &str = "https://api.telegram.org/";
+use crate::token::BotToken;
+
+const TELEGRAM_API_URL_DEFAULT: &str = "https://api.telegram.org/";
 
 /// Obtains URL to the Telegram Bot API. You're able to change this URL to point to fake Telegram server
 /// for E2E-testing by setting `TELEGRAM_API_URL` environment variable.
@@ -11,3 +12,95 @@ pub fn telegram_api_url() -> String {
         Err(_) => String::from(TELEGRAM_API_URL_DEFAULT),
     }
 }
+
+/// Location of the Bot API server used by a bot.
+///
+/// Defaults to the public server at `https://api.telegram.org/`,
+/// use [`from_env`](#method.from_env) to take the URL from the environment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApiUrl {
+    base_url: String,
+    file_base_url: Option<String>,
+    test_environment: bool,
+}
+
+impl ApiUrl {
+    /// Create a new `ApiUrl` pointing to the server at `base_url`, e.g. `https://api.telegram.org/`.
+    pub fn new<T: Into<String>>(base_url: T) -> Self {
+        ApiUrl {
+            base_url: with_trailing_slash(base_url.into()),
+            file_base_url: None,
+            test_environment: false,
+        }
+    }
+
+    /// Create a new `ApiUrl` pointing to the server obtained by
+    /// [`telegram_api_url`](fn.telegram_api_url.html), i.e. the `TELEGRAM_API_URL`
+    /// environment variable if it is set.
+    pub fn from_env() -> Self {
+        ApiUrl::new(telegram_api_url())
+    }
+
+    /// Set the base URL of the server.
+    pub fn base_url<T: Into<String>>(&mut self, base_url: T) -> &mut Self {
+        self.base_url = with_trailing_slash(base_url.into());
+        self
+    }
+
+    /// Set the base URL files are downloaded from.
+    ///
+    /// Defaults to the base URL of the server.
+    pub fn file_base_url<T: Into<String>>(&mut self, file_base_url: T) -> &mut Self {
+        self.file_base_url = Some(with_trailing_slash(file_base_url.into()));
+        self
+    }
+
+    /// Send requests to the Telegram test environment, i.e. `/bot<token>/test/<method>`.
+    pub fn test_environment(&mut self, test_environment: bool) -> &mut Self {
+        self.test_environment = test_environment;
+        self
+    }
+
+    /// Returns URL of the Bot API method.
+    pub fn method_url(&self, token: &BotToken, method: &str) -> String {
+        format!(
+            "{}bot{}/{}{}",
+            self.base_url,
+            token.expose(),
+            self.environment(),
+            method
+        )
+    }
+
+    /// Returns URL to download the file at `file_path` returned by `getFile`.
+    pub fn file_url(&self, token: &BotToken, file_path: &str) -> String {
+        format!(
+            "{}file/bot{}/{}{}",
+            self.file_base_url.as_ref().unwrap_or(&self.base_url),
+            token.expose(),
+            self.environment(),
+            file_path
+        )
+    }
+
+    fn environment(&self) -> &'static str {
+        if self.test_environment {
+            "test/"
+        } else {
+            ""
+        }
+    }
+}
+
+impl Default for ApiUrl {
+    fn default() -> Self {
+        ApiUrl::new(TELEGRAM_API_URL_DEFAULT)
+    }
+}
+
+fn with_trailing_slash(mut url: String) -> String {
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
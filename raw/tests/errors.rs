@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use telegram_bot_raw::{
+    ErrorCategory, HttpResponse, InlineKeyboardButton, JsonTrueToUnitResponse, ResponseType,
+    SupergroupId, MAX_CALLBACK_DATA_LEN,
+};
+
+fn category(body: &str) -> ErrorCategory {
+    let response = HttpResponse {
+        body: Some(body.as_bytes().to_vec()),
+    };
+    JsonTrueToUnitResponse::deserialize(response)
+        .unwrap_err()
+        .category()
+}
+
+#[test]
+fn forbidden() {
+    assert_eq!(
+        category(
+            r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was blocked by the user"}"#
+        ),
+        ErrorCategory::Forbidden
+    );
+}
+
+#[test]
+fn flood_wait() {
+    assert_eq!(
+        category(
+            r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 7","parameters":{"retry_after":7}}"#
+        ),
+        ErrorCategory::FloodWait(Duration::from_secs(7))
+    );
+}
+
+#[test]
+fn chat_migrated() {
+    assert_eq!(
+        category(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1001234567890}}"#
+        ),
+        ErrorCategory::ChatMigrated(SupergroupId::new(-1001234567890))
+    );
+}
+
+#[test]
+fn json() {
+    assert_eq!(category("<html></html>"), ErrorCategory::Json);
+}
+
+#[test]
+fn callback_data_too_long() {
+    assert!(InlineKeyboardButton::try_callback("ok", "x".repeat(MAX_CALLBACK_DATA_LEN)).is_ok());
+
+    let error = InlineKeyboardButton::try_callback("long", "x".repeat(65)).unwrap_err();
+    assert_eq!(error.category(), ErrorCategory::Other);
+    assert_eq!(
+        error.to_string(),
+        "callback data is 65 bytes long, the limit is 64 bytes"
+    );
+}
+
+#[test]
+#[should_panic(expected = "callback data is 65 bytes long")]
+fn unchecked_callback_data_too_long() {
+    InlineKeyboardButton::callback("long", "x".repeat(65));
+}
@@ -0,0 +1,40 @@
+use telegram_bot_raw::{
+    Body, ChatId, ChatRef, HttpRequest, InputFileUpload, Request, SendDocument, SendMessage,
+};
+
+fn chat(id: i64) -> Option<ChatRef> {
+    Some(ChatRef::Id(ChatId::new(id)))
+}
+
+#[test]
+fn set_chat_ref_json() {
+    let mut request = SendMessage::new(ChatId::new(-42), "text")
+        .serialize()
+        .unwrap();
+    assert_eq!(request.chat_ref(), chat(-42));
+
+    assert!(request.set_chat_ref(&ChatRef::Id(ChatId::new(-1000000000042))));
+    assert_eq!(request.chat_ref(), chat(-1000000000042));
+    match request.body {
+        Body::Json(ref body) => assert!(body.contains(r#""text":"text""#)),
+        ref body => panic!("unexpected body {:?}", body),
+    }
+}
+
+#[test]
+fn set_chat_ref_multipart() {
+    let mut request = SendDocument::new(ChatId::new(-42), InputFileUpload::with_path("a.txt"))
+        .serialize()
+        .unwrap();
+    assert_eq!(request.chat_ref(), chat(-42));
+
+    assert!(request.set_chat_ref(&ChatRef::Id(ChatId::new(-1000000000042))));
+    assert_eq!(request.chat_ref(), chat(-1000000000042));
+}
+
+#[test]
+fn set_chat_ref_without_chat() {
+    let mut request: HttpRequest = telegram_bot_raw::GetMe.serialize().unwrap();
+    assert_eq!(request.chat_ref(), None);
+    assert!(!request.set_chat_ref(&ChatRef::Id(ChatId::new(-42))));
+}
@@ -0,0 +1,12 @@
+use telegram_bot_raw::{ApiUrl, BotToken, RequestUrl};
+
+#[test]
+fn redacted() {
+    let token = BotToken::new("123456:ABC-DEF");
+    assert_eq!(token.to_string(), "123456:***");
+    assert_eq!(format!("{:?}", token), "BotToken(123456:***)");
+    assert_eq!(BotToken::new("secret").to_string(), "***");
+
+    let url = RequestUrl::method("getMe").url(&ApiUrl::new("https://api.telegram.org"), &token);
+    assert_eq!(url, "https://api.telegram.org/bot123456:ABC-DEF/getMe");
+}
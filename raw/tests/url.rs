@@ -0,0 +1,65 @@
+use telegram_bot_raw::{ApiUrl, BotToken};
+
+#[test]
+fn method_url() {
+    let token = BotToken::new("123:ABC");
+    let url = ApiUrl::new("http://localhost:8081");
+    assert_eq!(
+        url.method_url(&token, "getMe"),
+        "http://localhost:8081/bot123:ABC/getMe"
+    );
+    assert_eq!(
+        url.file_url(&token, "photos/file_1.jpg"),
+        "http://localhost:8081/file/bot123:ABC/photos/file_1.jpg"
+    );
+}
+
+#[test]
+fn test_environment() {
+    let token = BotToken::new("123:ABC");
+    let mut url = ApiUrl::new("https://api.telegram.org/");
+    url.test_environment(true);
+    assert_eq!(
+        url.method_url(&token, "getMe"),
+        "https://api.telegram.org/bot123:ABC/test/getMe"
+    );
+    assert_eq!(
+        url.file_url(&token, "photos/file_1.jpg"),
+        "https://api.telegram.org/file/bot123:ABC/test/photos/file_1.jpg"
+    );
+}
+
+#[test]
+fn file_base_url() {
+    let token = BotToken::new("123:ABC");
+    let mut url = ApiUrl::new("https://api.telegram.org");
+    url.file_base_url("https://files.example.com");
+    assert_eq!(
+        url.method_url(&token, "getMe"),
+        "https://api.telegram.org/bot123:ABC/getMe"
+    );
+    assert_eq!(
+        url.file_url(&token, "photos/file_1.jpg"),
+        "https://files.example.com/file/bot123:ABC/photos/file_1.jpg"
+    );
+
+    url.base_url("http://localhost:8081/");
+    assert_eq!(
+        url.method_url(&token, "getMe"),
+        "http://localhost:8081/bot123:ABC/getMe"
+    );
+}
+
+#[test]
+fn default_url() {
+    std::env::set_var("TELEGRAM_API_URL", "http://localhost:8081");
+    let token = BotToken::new("123:ABC");
+    assert_eq!(
+        ApiUrl::default().method_url(&token, "getMe"),
+        "https://api.telegram.org/bot123:ABC/getMe"
+    );
+    assert_eq!(
+        ApiUrl::from_env().method_url(&token, "getMe"),
+        "http://localhost:8081/bot123:ABC/getMe"
+    );
+}